@@ -10,7 +10,7 @@ in your Gmail account. This app runs on your desktop and does not send your emai
 Therefore it is the most secure way of cleaning up your Gmail account. You don't need to give any permissions \
 to this app, neither do you need to provide credentials. When you start it, a browser window opens up \
 with Gmail's login page. Once Gmail authorizes you the app visually shows you what you can delete. \
-The app does not read your email or cannot modify your Gmail account in any way.";
+By default the app only gets read-only access and cannot modify your Gmail account in any way.";
         div()
             .bg(rgb(0xffffff))
             .w_full()
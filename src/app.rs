@@ -4,10 +4,20 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use tokio;
 
+use crate::cache::MessageCache;
+use crate::scan::ScanSessions;
 use crate::server::{
-    appjs, chart_sectionjs, error, headerjs, home, index, login, logo, mainjs, oauth2_callback,
-    profile, searchjs, summary, tab_sectionjs, utilsjs,
+    age, age_sectionjs, appjs, apply_label, archive, attachment_sectionjs, attachment_types,
+    attachments, buckets, bulk_trash, chart_sectionjs, cleanup_sectionjs, create_filter,
+    download_attachments, duplicate_sectionjs, duplicates, error, export, export_messages,
+    filter_sectionjs, headerjs, home, index, insights_sectionjs, journal, label_sectionjs, labels,
+    login, logo, mainjs, messages, newsletter_sectionjs, newsletters, oauth2_callback, permissions,
+    profile, report, rule_categories, rule_messages, rule_sectionjs, rules, searchjs,
+    sender_messages, sender_sectionjs, senders, snapshots, snapshots_diff, strip_attachments,
+    summary, sync_mailbox, tab_sectionjs, thread_detail, thread_sectionjs, threads, trash,
+    trend_sectionjs, undo, unsubscribe, unsubscribe_preview, update_buckets, update_rules, utilsjs,
 };
+use crate::settings::SettingsStore;
 
 fn init_handlebars() -> Handlebars<'static> {
     let mut handlebars = Handlebars::new();
@@ -18,6 +28,9 @@ fn init_handlebars() -> Handlebars<'static> {
         .register_template_string("error", include_str!("../templates/error.html.hbs"))
         .expect("Failed to register error template");
     handlebars
+        .register_template_string("report", include_str!("../templates/report.html.hbs"))
+        .expect("Failed to register report template");
+    handlebars
 }
 
 pub struct AppState {
@@ -46,22 +59,72 @@ impl AppState {
                     .merge(("secret_key", include_str!("../rocket_secret_key")));
                 let rocket = rocket::custom(figment)
                     .manage(handlebars)
+                    .manage(ScanSessions::new())
+                    .manage(MessageCache::open())
+                    .manage(SettingsStore::load())
                     .mount(
                         "/",
                         routes![
                             index,
                             summary,
+                            messages,
+                            export,
+                            sync_mailbox,
+                            snapshots,
+                            snapshots_diff,
+                            threads,
+                            thread_detail,
+                            senders,
+                            sender_messages,
+                            attachments,
+                            attachment_types,
+                            download_attachments,
+                            strip_attachments,
+                            duplicates,
+                            labels,
+                            age,
+                            newsletters,
+                            unsubscribe_preview,
+                            unsubscribe,
+                            buckets,
+                            update_buckets,
+                            rules,
+                            update_rules,
+                            permissions,
+                            trash,
+                            bulk_trash,
+                            export_messages,
+                            archive,
+                            apply_label,
+                            create_filter,
+                            journal,
+                            undo,
+                            rule_categories,
+                            rule_messages,
                             oauth2_callback,
                             login,
                             error,
                             home,
                             profile,
+                            report,
                             mainjs,
                             appjs,
                             headerjs,
                             chart_sectionjs,
                             tab_sectionjs,
                             searchjs,
+                            newsletter_sectionjs,
+                            cleanup_sectionjs,
+                            filter_sectionjs,
+                            rule_sectionjs,
+                            trend_sectionjs,
+                            insights_sectionjs,
+                            thread_sectionjs,
+                            sender_sectionjs,
+                            attachment_sectionjs,
+                            duplicate_sectionjs,
+                            label_sectionjs,
+                            age_sectionjs,
                             utilsjs,
                             logo
                         ],
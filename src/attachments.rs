@@ -0,0 +1,451 @@
+use crate::cache::CachedMessage;
+use crate::gmail_client::{attachment_get, decode_base64url, message_get, MessagePart};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the file listing what was saved to a folder.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// A file attached to a message, found in its MIME tree.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub part_id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub attachment_id: Option<String>,
+}
+
+/// Recursively walks a message payload and collects every part which
+/// carries a filename.
+pub fn collect_attachments(part: &MessagePart, attachments: &mut Vec<Attachment>) {
+    if !part.filename.is_empty() {
+        attachments.push(Attachment {
+            part_id: part.partId.clone(),
+            filename: part.filename.clone(),
+            mime_type: part.mimeType.to_lowercase(),
+            size: part.body.size as i64,
+            attachment_id: part.body.attachmentId.clone(),
+        });
+    }
+    if let Some(parts) = &part.parts {
+        for child in parts {
+            collect_attachments(child, attachments);
+        }
+    }
+}
+
+/// Returns the broad type an attachment is totalled under, from its MIME
+/// type or, for generic MIME types, its file extension.
+pub fn attachment_kind(attachment: &Attachment) -> &'static str {
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+    let mime_type = attachment.mime_type.as_str();
+    if mime_type == "application/pdf" || extension == "pdf" {
+        "pdf"
+    } else if mime_type.starts_with("image/") {
+        "image"
+    } else if mime_type.starts_with("video/") {
+        "video"
+    } else if mime_type.starts_with("audio/") {
+        "audio"
+    } else if mime_type.contains("zip")
+        || mime_type.contains("compressed")
+        || mime_type.contains("tar")
+        || mime_type.contains("rar")
+        || ["zip", "7z", "rar", "gz", "tgz", "tar", "bz2", "xz"].contains(&extension.as_str())
+    {
+        "archive"
+    } else {
+        "other"
+    }
+}
+
+/// An attachment together with the message it belongs to.
+#[derive(Serialize)]
+pub struct AttachmentEntry {
+    pub message_id: String,
+    pub thread_id: String,
+    pub subject: String,
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub attachment: Attachment,
+}
+
+/// Number and total size of the attachments of one type.
+#[derive(Serialize)]
+pub struct KindTotal {
+    pub kind: &'static str,
+    pub count: usize,
+    pub total_size: i64,
+}
+
+/// Lists the attachments of all the messages, sorted by `sort` which is
+/// one of "size" (the default, largest first), "name" or "type".
+pub fn inventory(messages: &[CachedMessage], sort: &str) -> Vec<AttachmentEntry> {
+    let mut entries: Vec<AttachmentEntry> = messages
+        .iter()
+        .flat_map(|message| {
+            message
+                .attachments
+                .iter()
+                .map(|attachment| AttachmentEntry {
+                    message_id: message.id.clone(),
+                    thread_id: message.thread_id.clone(),
+                    subject: message.subject.clone(),
+                    kind: attachment_kind(attachment),
+                    attachment: attachment.clone(),
+                })
+        })
+        .collect();
+    match sort {
+        "name" => entries.sort_by(|a, b| {
+            a.attachment
+                .filename
+                .to_lowercase()
+                .cmp(&b.attachment.filename.to_lowercase())
+        }),
+        "type" => entries.sort_by(|a, b| {
+            a.attachment
+                .mime_type
+                .cmp(&b.attachment.mime_type)
+                .then(b.attachment.size.cmp(&a.attachment.size))
+        }),
+        _ => entries.sort_by_key(|entry| Reverse(entry.attachment.size)),
+    }
+    entries
+}
+
+/// Totals attachments per type, largest total first.
+pub fn totals_by_kind(entries: &[AttachmentEntry]) -> Vec<KindTotal> {
+    let mut kinds: HashMap<&'static str, KindTotal> = HashMap::new();
+    for entry in entries {
+        let total = kinds.entry(entry.kind).or_insert(KindTotal {
+            kind: entry.kind,
+            count: 0,
+            total_size: 0,
+        });
+        total.count += 1;
+        total.total_size += entry.attachment.size;
+    }
+    let mut totals: Vec<KindTotal> = kinds.into_values().collect();
+    totals.sort_by_key(|total| Reverse(total.total_size));
+    totals
+}
+
+/// An attachment saved to a local folder, as listed in its manifest.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedAttachment {
+    pub message_id: String,
+    pub thread_id: String,
+    pub part_id: String,
+    pub subject: String,
+    pub sender: String,
+    /// Milliseconds since the epoch the message was received.
+    pub date: i64,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: i64,
+    /// Where the file was written.
+    pub path: String,
+    pub sha256: String,
+    /// Milliseconds since the epoch the file was written.
+    pub saved_at: i64,
+}
+
+#[derive(Serialize)]
+pub struct SaveReport {
+    pub folder: String,
+    pub manifest: String,
+    pub saved: Vec<SavedAttachment>,
+    /// Attachments which could not be saved, as "message ID/part ID".
+    pub failed: Vec<String>,
+}
+
+/// Makes an attachment name safe to use as a file name on any platform.
+pub fn safe_filename(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_matches('.');
+    if name.is_empty() {
+        "attachment".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Returns a path for `filename` in `folder` which does not exist yet,
+/// numbering the name like "report (2).pdf" if needed. The manifest's
+/// name is never returned, even before the manifest is written.
+fn unique_path(folder: &Path, filename: &str) -> PathBuf {
+    let (stem, extension) = match filename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (filename, String::new()),
+    };
+    let taken = |path: &Path| {
+        path.exists()
+            || path
+                .file_name()
+                .map(|name| name.to_string_lossy().eq_ignore_ascii_case(MANIFEST_FILE))
+                .unwrap_or(false)
+    };
+    let mut path = folder.join(filename);
+    let mut n = 2;
+    while taken(&path) {
+        path = folder.join(format!("{} ({}){}", stem, n, extension));
+        n += 1;
+    }
+    path
+}
+
+fn find_part<'a>(part: &'a MessagePart, part_id: &str) -> Option<&'a MessagePart> {
+    if part.partId == part_id {
+        return Some(part);
+    }
+    part.parts
+        .iter()
+        .flatten()
+        .find_map(|child| find_part(child, part_id))
+}
+
+/// Downloads the content of an attachment. Attachment IDs in the cache can
+/// go stale and small parts carry their data inline, so the message is
+/// fetched again when the cached ID does not work.
+pub async fn attachment_content(
+    token: &str,
+    message_id: &str,
+    attachment: &Attachment,
+) -> Result<Vec<u8>, ()> {
+    if let Some(attachment_id) = &attachment.attachment_id {
+        if let Ok(content) = attachment_get(token, message_id, attachment_id).await {
+            return Ok(content);
+        }
+    }
+    let message = message_get(token, message_id).await?;
+    let payload = message.payload.ok_or(())?;
+    let part = find_part(&payload, &attachment.part_id).ok_or(())?;
+    match (&part.body.data, &part.body.attachmentId) {
+        (Some(data), _) => decode_base64url(data),
+        (None, Some(attachment_id)) => attachment_get(token, message_id, attachment_id).await,
+        (None, None) => Err(()),
+    }
+}
+
+/// Reads the manifest of a folder, or an empty one if there is none yet.
+fn read_manifest(path: &Path) -> Vec<SavedAttachment> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Saves the `selected` attachments, given as message ID and part ID, to
+/// `folder` and records them in its manifest. Attachments the manifest
+/// already lists are not downloaded again.
+pub async fn save_attachments(
+    token: &str,
+    messages: &[CachedMessage],
+    selected: &[(String, String)],
+    folder: &Path,
+) -> SaveReport {
+    let manifest_path = folder.join(MANIFEST_FILE);
+    let mut manifest = read_manifest(&manifest_path);
+    let mut saved = vec![];
+    let mut failed = vec![];
+    for (message_id, part_id) in selected {
+        let key = format!("{}/{}", message_id, part_id);
+        let previous = manifest.iter().find(|entry| {
+            entry.message_id == *message_id
+                && entry.part_id == *part_id
+                && Path::new(&entry.path).exists()
+        });
+        if let Some(previous) = previous {
+            saved.push(previous.clone());
+            continue;
+        }
+        let found = messages
+            .iter()
+            .find(|message| message.id == *message_id)
+            .and_then(|message| {
+                message
+                    .attachments
+                    .iter()
+                    .find(|attachment| attachment.part_id == *part_id)
+                    .map(|attachment| (message, attachment))
+            });
+        let Some((message, attachment)) = found else {
+            failed.push(key);
+            continue;
+        };
+        let content = match attachment_content(token, message_id, attachment).await {
+            Ok(content) => content,
+            Err(_) => {
+                failed.push(key);
+                continue;
+            }
+        };
+        let path = unique_path(folder, &safe_filename(&attachment.filename));
+        if let Err(e) = std::fs::write(&path, &content) {
+            println!("Could not write {}: {}", path.display(), e);
+            failed.push(key);
+            continue;
+        }
+        let entry = SavedAttachment {
+            message_id: message.id.clone(),
+            thread_id: message.thread_id.clone(),
+            part_id: attachment.part_id.clone(),
+            subject: message.subject.clone(),
+            sender: message.sender.clone(),
+            date: message.date,
+            filename: attachment.filename.clone(),
+            mime_type: attachment.mime_type.clone(),
+            size: content.len() as i64,
+            path: path.display().to_string(),
+            sha256: format!("{:x}", Sha256::digest(&content)),
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0),
+        };
+        manifest.push(entry.clone());
+        saved.push(entry);
+    }
+    match serde_json::to_string_pretty(&manifest) {
+        Ok(data) => {
+            if let Err(e) = std::fs::write(&manifest_path, data) {
+                println!("Could not write the manifest: {}", e);
+            }
+        }
+        Err(e) => println!("Could not serialize the manifest: {}", e),
+    }
+    SaveReport {
+        folder: folder.display().to_string(),
+        manifest: manifest_path.display().to_string(),
+        saved,
+        failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh folder under the system temporary folder.
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!(
+            "gmail-cleaner-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    #[test]
+    fn replaces_path_separators_and_reserved_characters() {
+        assert_eq!(safe_filename("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(safe_filename("..\\windows\\win.ini"), "_windows_win.ini");
+        assert_eq!(safe_filename("C:report?.pdf"), "C_report_.pdf");
+        assert_eq!(safe_filename("a<b>|\"c\"*.txt"), "a_b___c__.txt");
+        assert_eq!(safe_filename("line\nbreak\u{0}.txt"), "line_break_.txt");
+        assert_eq!(safe_filename("rapport d'été.pdf"), "rapport d'été.pdf");
+    }
+
+    #[test]
+    fn never_returns_an_empty_or_dot_name() {
+        for name in ["", "   ", ".", "..", "...", " .. "] {
+            assert_eq!(safe_filename(name), "attachment", "{:?}", name);
+        }
+        assert_eq!(safe_filename(".hidden"), "hidden");
+    }
+
+    #[test]
+    fn numbers_names_which_are_taken() {
+        let folder = temp_folder("unique");
+        assert_eq!(
+            unique_path(&folder, "report.pdf"),
+            folder.join("report.pdf")
+        );
+        std::fs::write(folder.join("report.pdf"), "1").unwrap();
+        assert_eq!(
+            unique_path(&folder, "report.pdf"),
+            folder.join("report (2).pdf")
+        );
+        std::fs::write(folder.join("report (2).pdf"), "2").unwrap();
+        assert_eq!(
+            unique_path(&folder, "report.pdf"),
+            folder.join("report (3).pdf")
+        );
+        std::fs::write(folder.join("README"), "").unwrap();
+        assert_eq!(unique_path(&folder, "README"), folder.join("README (2)"));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_manifest_name_free() {
+        let folder = temp_folder("manifest");
+        assert_eq!(
+            unique_path(&folder, MANIFEST_FILE),
+            folder.join("manifest (2).json")
+        );
+        assert_eq!(
+            unique_path(&folder, "Manifest.JSON"),
+            folder.join("Manifest (2).JSON")
+        );
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_attachments_the_manifest_lists() {
+        let folder = temp_folder("skip");
+        let saved_path = folder.join("report.pdf");
+        std::fs::write(&saved_path, "%PDF").unwrap();
+        let listed = SavedAttachment {
+            message_id: "m1".to_string(),
+            thread_id: "t1".to_string(),
+            part_id: "1".to_string(),
+            subject: "Report".to_string(),
+            sender: "a@example.com".to_string(),
+            date: 0,
+            filename: "report.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size: 4,
+            path: saved_path.display().to_string(),
+            sha256: String::new(),
+            saved_at: 0,
+        };
+        std::fs::write(
+            folder.join(MANIFEST_FILE),
+            serde_json::to_string(&[&listed]).unwrap(),
+        )
+        .unwrap();
+
+        // Nothing is downloaded: the listed attachment is reported as saved
+        // and the one which is not cached fails without a request
+        let selected = [
+            ("m1".to_string(), "1".to_string()),
+            ("m2".to_string(), "1".to_string()),
+        ];
+        let report = save_attachments("", &[], &selected, &folder).await;
+        assert_eq!(report.saved.len(), 1);
+        assert_eq!(report.saved[0].path, listed.path);
+        assert_eq!(report.failed, ["m2/1"]);
+        assert_eq!(read_manifest(&folder.join(MANIFEST_FILE)).len(), 1);
+        assert_eq!(std::fs::read_to_string(&saved_path).unwrap(), "%PDF");
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
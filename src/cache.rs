@@ -0,0 +1,487 @@
+use crate::attachments::{collect_attachments, Attachment};
+use crate::gmail_client::Message;
+use crate::journal::JournalEntry;
+use crate::settings::app_data_dir;
+use crate::snapshot::Snapshot;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::sync::Mutex;
+
+/// The metadata kept locally for every message which has been sized.
+#[derive(Clone)]
+pub struct CachedMessage {
+    pub id: String,
+    pub thread_id: String,
+    pub subject: String,
+    pub sender: String,
+    /// Milliseconds since the epoch, from `Message.internalDate`.
+    pub date: i64,
+    pub labels: Vec<String>,
+    pub size_estimate: i32,
+    pub attachments: Vec<Attachment>,
+    /// Mailing list headers, empty when the message has none.
+    pub list_id: String,
+    pub list_unsubscribe: String,
+    pub list_unsubscribe_post: String,
+    pub precedence: String,
+}
+
+impl CachedMessage {
+    pub fn from_message(msg: Message) -> Self {
+        let mut subject = String::new();
+        let mut sender = String::new();
+        let mut attachments = Vec::new();
+        let mut list_id = String::new();
+        let mut list_unsubscribe = String::new();
+        let mut list_unsubscribe_post = String::new();
+        let mut precedence = String::new();
+        if let Some(payload) = &msg.payload {
+            for header in &payload.headers {
+                if header.name.eq_ignore_ascii_case("Subject") {
+                    subject = header.value.clone();
+                } else if header.name.eq_ignore_ascii_case("From") {
+                    sender = header.value.clone();
+                } else if header.name.eq_ignore_ascii_case("List-Id") {
+                    list_id = header.value.clone();
+                } else if header.name.eq_ignore_ascii_case("List-Unsubscribe") {
+                    list_unsubscribe = header.value.clone();
+                } else if header.name.eq_ignore_ascii_case("List-Unsubscribe-Post") {
+                    list_unsubscribe_post = header.value.clone();
+                } else if header.name.eq_ignore_ascii_case("Precedence") {
+                    precedence = header.value.clone();
+                }
+            }
+            collect_attachments(payload, &mut attachments);
+        }
+        Self {
+            id: msg.id,
+            thread_id: msg.threadId,
+            subject,
+            sender,
+            date: msg.internalDate.parse().unwrap_or(0),
+            labels: msg.labelIds,
+            size_estimate: msg.sizeEstimate,
+            attachments,
+            list_id,
+            list_unsubscribe,
+            list_unsubscribe_post,
+            precedence,
+        }
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let labels: String = row.get("labels")?;
+        let attachments: String = row.get("attachments")?;
+        Ok(Self {
+            id: row.get("id")?,
+            thread_id: row.get("thread_id")?,
+            subject: row.get("subject")?,
+            sender: row.get("sender")?,
+            date: row.get("date")?,
+            labels: serde_json::from_str(&labels).unwrap_or_default(),
+            size_estimate: row.get("size_estimate")?,
+            attachments: serde_json::from_str(&attachments).unwrap_or_default(),
+            list_id: row.get("list_id")?,
+            list_unsubscribe: row.get("list_unsubscribe")?,
+            list_unsubscribe_post: row.get("list_unsubscribe_post")?,
+            precedence: row.get("precedence")?,
+        })
+    }
+}
+
+/// Version of the cache schema, kept in SQLite's `user_version`.
+/// 1 added the attachment inventory, 2 the mailing list headers.
+const SCHEMA_VERSION: i64 = 2;
+
+const MESSAGES_TABLE: &str = "CREATE TABLE IF NOT EXISTS messages (
+    account TEXT NOT NULL,
+    id TEXT NOT NULL,
+    thread_id TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    sender TEXT NOT NULL,
+    date INTEGER NOT NULL,
+    labels TEXT NOT NULL,
+    size_estimate INTEGER NOT NULL,
+    attachment_count INTEGER NOT NULL,
+    attachment_size INTEGER NOT NULL,
+    attachments TEXT NOT NULL DEFAULT '[]',
+    list_id TEXT NOT NULL DEFAULT '',
+    list_unsubscribe TEXT NOT NULL DEFAULT '',
+    list_unsubscribe_post TEXT NOT NULL DEFAULT '',
+    precedence TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (account, id)
+);";
+
+/// Columns of the messages table added after its first version.
+const ADDED_COLUMNS: [(&str, &str); 5] = [
+    ("attachments", "TEXT NOT NULL DEFAULT '[]'"),
+    ("list_id", "TEXT NOT NULL DEFAULT ''"),
+    ("list_unsubscribe", "TEXT NOT NULL DEFAULT ''"),
+    ("list_unsubscribe_post", "TEXT NOT NULL DEFAULT ''"),
+    ("precedence", "TEXT NOT NULL DEFAULT ''"),
+];
+
+/// Upgrades a cache written by an earlier version. Messages cached before
+/// the newer columns existed only hold their defaults, so they are dropped
+/// to be fetched again, and the stored history IDs are forgotten so that
+/// the next sync is a full one.
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    let columns = conn
+        .prepare("PRAGMA table_info(messages)")?
+        .query_map([], |row| row.get::<_, String>("name"))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let tx = conn.unchecked_transaction()?;
+    for (name, definition) in ADDED_COLUMNS {
+        if !columns.iter().any(|column| column == name) {
+            tx.execute(
+                &format!("ALTER TABLE messages ADD COLUMN {} {}", name, definition),
+                [],
+            )?;
+        }
+    }
+    let stale = tx.execute("DELETE FROM messages", [])?;
+    tx.execute("UPDATE accounts SET history_id = NULL", [])?;
+    tx.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+    tx.commit()?;
+    if stale > 0 {
+        println!(
+            "cache: {} messages cached by an earlier version will be fetched again",
+            stale
+        );
+    }
+    Ok(())
+}
+
+/// Per-account message metadata persisted in a local SQLite database,
+/// so that messages sized in earlier sessions are not downloaded again.
+pub struct MessageCache {
+    conn: Mutex<Connection>,
+}
+
+impl MessageCache {
+    /// Opens the cache in the user's data directory, falling back to an
+    /// in-memory database if that is not possible.
+    pub fn open() -> Self {
+        let conn = match app_data_dir().map(|dir| Connection::open(dir.join("cache.sqlite"))) {
+            Some(Ok(conn)) => conn,
+            Some(Err(e)) => {
+                println!("cache error: {}", e);
+                Connection::open_in_memory().expect("Failed to open in-memory cache")
+            }
+            None => Connection::open_in_memory().expect("Failed to open in-memory cache"),
+        };
+        Self::with_connection(conn)
+    }
+
+    /// Creates the cache tables on `conn` if needed and brings databases
+    /// written by earlier versions up to date.
+    fn with_connection(conn: Connection) -> Self {
+        conn.execute_batch(&format!(
+            "{}
+            CREATE TABLE IF NOT EXISTS accounts (
+                account TEXT PRIMARY KEY,
+                history_id TEXT
+            );
+            CREATE TABLE IF NOT EXISTS snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account TEXT NOT NULL,
+                taken_at INTEGER NOT NULL,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS journal (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                account TEXT NOT NULL,
+                performed_at INTEGER NOT NULL,
+                undone INTEGER NOT NULL DEFAULT 0,
+                data TEXT NOT NULL
+            );",
+            MESSAGES_TABLE
+        ))
+        .expect("Failed to create cache tables");
+        if let Err(e) = migrate(&conn) {
+            // Start over with an empty message table rather than one
+            // missing columns; messages are fetched again as they are seen
+            println!("cache error: {}, clearing the message cache", e);
+            conn.execute_batch(&format!(
+                "DROP TABLE IF EXISTS messages;
+                {}
+                UPDATE accounts SET history_id = NULL;
+                PRAGMA user_version = {};",
+                MESSAGES_TABLE, SCHEMA_VERSION
+            ))
+            .expect("Failed to recreate the message cache");
+        }
+        Self {
+            conn: Mutex::new(conn),
+        }
+    }
+
+    pub fn get(&self, account: &str, id: &str) -> Option<CachedMessage> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT * FROM messages WHERE account = ?1 AND id = ?2",
+            params![account, id],
+            CachedMessage::from_row,
+        )
+        .optional()
+        .unwrap_or_else(|e| {
+            println!("cache error: {}", e);
+            None
+        })
+    }
+
+    pub fn put(&self, account: &str, message: &CachedMessage) {
+        let conn = self.conn.lock().unwrap();
+        let labels = serde_json::to_string(&message.labels).unwrap_or_default();
+        let attachments = serde_json::to_string(&message.attachments).unwrap_or_default();
+        let attachment_size: i64 = message.attachments.iter().map(|a| a.size).sum();
+        if let Err(e) = conn.execute(
+            "INSERT OR REPLACE INTO messages (account, id, thread_id, subject, sender, date,
+                labels, size_estimate, attachment_count, attachment_size, attachments,
+                list_id, list_unsubscribe, list_unsubscribe_post, precedence)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                account,
+                message.id,
+                message.thread_id,
+                message.subject,
+                message.sender,
+                message.date,
+                labels,
+                message.size_estimate,
+                message.attachments.len() as i64,
+                attachment_size,
+                attachments,
+                message.list_id,
+                message.list_unsubscribe,
+                message.list_unsubscribe_post,
+                message.precedence,
+            ],
+        ) {
+            println!("cache error: {}", e);
+        }
+    }
+
+    pub fn remove(&self, account: &str, id: &str) {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "DELETE FROM messages WHERE account = ?1 AND id = ?2",
+            params![account, id],
+        ) {
+            println!("cache error: {}", e);
+        }
+    }
+
+    /// Removes every cached message of a thread.
+    pub fn remove_thread(&self, account: &str, thread_id: &str) {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "DELETE FROM messages WHERE account = ?1 AND thread_id = ?2",
+            params![account, thread_id],
+        ) {
+            println!("cache error: {}", e);
+        }
+    }
+
+    /// Returns every cached message of the account.
+    pub fn all(&self, account: &str) -> Vec<CachedMessage> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .prepare("SELECT * FROM messages WHERE account = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![account], CachedMessage::from_row)?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            });
+        result.unwrap_or_else(|e| {
+            println!("cache error: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Returns the history ID the cache of the account is up to date with.
+    pub fn history_id(&self, account: &str) -> Option<String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT history_id FROM accounts WHERE account = ?1",
+            params![account],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap_or(None)
+    }
+
+    pub fn set_history_id(&self, account: &str, history_id: &str) {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "INSERT INTO accounts (account, history_id) VALUES (?1, ?2)
+             ON CONFLICT(account) DO UPDATE SET history_id = ?2",
+            params![account, history_id],
+        ) {
+            println!("cache error: {}", e);
+        }
+    }
+
+    pub fn add_snapshot(&self, account: &str, snapshot: &Snapshot) {
+        let conn = self.conn.lock().unwrap();
+        let data = serde_json::to_string(snapshot).unwrap_or_default();
+        if let Err(e) = conn.execute(
+            "INSERT INTO snapshots (account, taken_at, data) VALUES (?1, ?2, ?3)",
+            params![account, snapshot.taken_at, data],
+        ) {
+            println!("cache error: {}", e);
+        }
+    }
+
+    /// Returns the snapshots of the account, oldest first.
+    pub fn snapshots(&self, account: &str) -> Vec<Snapshot> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .prepare("SELECT id, data FROM snapshots WHERE account = ?1 ORDER BY taken_at")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![account], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            });
+        match result {
+            Ok(rows) => rows
+                .into_iter()
+                .filter_map(|(id, data)| {
+                    let mut snapshot: Snapshot = serde_json::from_str(&data).ok()?;
+                    snapshot.id = id;
+                    Some(snapshot)
+                })
+                .collect(),
+            Err(e) => {
+                println!("cache error: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Records a modifying action and returns its ID.
+    pub fn add_journal_entry(&self, account: &str, entry: &JournalEntry) -> i64 {
+        let conn = self.conn.lock().unwrap();
+        let data = serde_json::to_string(entry).unwrap_or_default();
+        match conn.execute(
+            "INSERT INTO journal (account, performed_at, data) VALUES (?1, ?2, ?3)",
+            params![account, entry.performed_at, data],
+        ) {
+            Ok(_) => conn.last_insert_rowid(),
+            Err(e) => {
+                println!("cache error: {}", e);
+                0
+            }
+        }
+    }
+
+    /// Returns the journal of the account, most recent action first.
+    pub fn journal(&self, account: &str) -> Vec<JournalEntry> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .prepare(
+                "SELECT id, undone, data FROM journal WHERE account = ?1
+                 ORDER BY performed_at DESC, id DESC",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(params![account], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, bool>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            });
+        match result {
+            Ok(rows) => rows
+                .into_iter()
+                .filter_map(|(id, undone, data)| {
+                    let mut entry: JournalEntry = serde_json::from_str(&data).ok()?;
+                    entry.id = id;
+                    entry.undone = undone;
+                    Some(entry)
+                })
+                .collect(),
+            Err(e) => {
+                println!("cache error: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn set_journal_undone(&self, account: &str, id: i64) {
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute(
+            "UPDATE journal SET undone = 1 WHERE account = ?1 AND id = ?2",
+            params![account, id],
+        ) {
+            println!("cache error: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrades_a_cache_without_the_newer_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (
+                account TEXT NOT NULL,
+                id TEXT NOT NULL,
+                thread_id TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                date INTEGER NOT NULL,
+                labels TEXT NOT NULL,
+                size_estimate INTEGER NOT NULL,
+                attachment_count INTEGER NOT NULL,
+                attachment_size INTEGER NOT NULL,
+                PRIMARY KEY (account, id)
+            );
+            CREATE TABLE accounts (account TEXT PRIMARY KEY, history_id TEXT);
+            INSERT INTO messages VALUES ('a', '1', 't', 's', 'f', 0, '[]', 10, 1, 5);
+            INSERT INTO accounts VALUES ('a', '42');",
+        )
+        .unwrap();
+
+        let cache = MessageCache::with_connection(conn);
+        assert!(cache.all("a").is_empty());
+        assert_eq!(cache.history_id("a"), None);
+        let conn = cache.conn.lock().unwrap();
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn keeps_messages_of_an_up_to_date_cache() {
+        let cache = MessageCache::with_connection(Connection::open_in_memory().unwrap());
+        let message = CachedMessage {
+            id: "1".to_string(),
+            thread_id: "t".to_string(),
+            subject: "s".to_string(),
+            sender: "f".to_string(),
+            date: 0,
+            labels: vec![],
+            size_estimate: 10,
+            attachments: vec![],
+            list_id: "list.example.com".to_string(),
+            list_unsubscribe: String::new(),
+            list_unsubscribe_post: String::new(),
+            precedence: String::new(),
+        };
+        cache.put("a", &message);
+        let conn = cache.conn.into_inner().unwrap();
+        let cache = MessageCache::with_connection(conn);
+        assert_eq!(cache.all("a")[0].list_id, "list.example.com");
+    }
+}
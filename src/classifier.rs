@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+/// A named size range; it covers sizes from `min_size` up to the
+/// `min_size` of the next bucket.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SizeBucket {
+    pub name: String,
+    /// Inclusive lower bound in bytes.
+    pub min_size: i64,
+}
+
+/// Classifies messages into any number of named size buckets.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SizeClassifier {
+    pub buckets: Vec<SizeBucket>,
+}
+
+impl Default for SizeClassifier {
+    fn default() -> Self {
+        Self {
+            buckets: vec![
+                SizeBucket {
+                    name: "small".to_string(),
+                    min_size: 0,
+                },
+                SizeBucket {
+                    name: "medium".to_string(),
+                    min_size: 100 * 1024,
+                },
+                SizeBucket {
+                    name: "large".to_string(),
+                    min_size: 1024 * 1024,
+                },
+            ],
+        }
+    }
+}
+
+impl SizeClassifier {
+    /// Sorts the buckets by lower bound and checks that they cover every
+    /// size with unique, non-empty names.
+    pub fn validate(mut self) -> Result<Self, String> {
+        if self.buckets.is_empty() {
+            return Err("At least one bucket is needed".to_string());
+        }
+        self.buckets.sort_by_key(|bucket| bucket.min_size);
+        if self.buckets[0].min_size != 0 {
+            return Err("The smallest bucket must start at 0 bytes".to_string());
+        }
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            if bucket.name.trim().is_empty() {
+                return Err("Bucket names cannot be empty".to_string());
+            }
+            if self.buckets[..index].iter().any(|b| b.name == bucket.name) {
+                return Err(format!("Bucket name {} is used twice", bucket.name));
+            }
+            if index > 0 && self.buckets[index - 1].min_size == bucket.min_size {
+                return Err(format!(
+                    "Buckets {} and {} start at the same size",
+                    self.buckets[index - 1].name,
+                    bucket.name
+                ));
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns the name of the bucket a message of `size` bytes falls in.
+    pub fn classify(&self, size: i64) -> &str {
+        self.buckets
+            .iter()
+            .rev()
+            .find(|bucket| size >= bucket.min_size)
+            .or(self.buckets.first())
+            .map(|bucket| bucket.name.as_str())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,612 @@
+use crate::app::AppState;
+use crate::cache::{CachedMessage, MessageCache};
+use crate::export::{to_csv, to_ndjson, ExportRow};
+use crate::gmail_client::{labels_list, message_get, messages_list, profile_get};
+use crate::report::{ReportRow, StorageReport};
+use crate::server::{
+    random_string, Credentials, TokenResponse, MODIFY_SCOPE, READONLY_SCOPE, SETTINGS_SCOPE,
+};
+use crate::settings::{app_data_dir, SettingsStore};
+use crate::stats::{format_size, iso_date};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "Usage: gmail-cleaner-rs <command> [options]
+
+Commands:
+  login [--modify] [--filters] [--manual]
+                                    Sign in to Gmail and keep the token for later commands.
+                                    --modify also grants cleanup access, --filters
+                                    cleanup access and permission to create filters.
+                                    --manual asks for the address the browser was sent
+                                    to instead of waiting for it, for machines without
+                                    a browser.
+  logout                            Forget the saved token.
+  scan [--query Q] [--max N] [--json]
+                                    Fetch up to N messages (default 100) matching a Gmail
+                                    search into the local cache and list them.
+  report [--json]                   Summarize the storage used by the cached messages.
+  export [--format csv|json|ndjson] [--output FILE]
+                                    Write every cached message to FILE or stdout.
+  serve                             Run the web interface on http://127.0.0.1:5000
+                                    without a window, until interrupted.
+  help                              Show this message.
+";
+
+/// The token saved by `login`, in the app data directory.
+#[derive(Serialize, Deserialize)]
+struct SavedToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Milliseconds since the epoch.
+    expires_at: i64,
+    scope: String,
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Options given to a command, `--name value` or a bare `--switch`.
+struct Options {
+    values: HashMap<String, String>,
+}
+
+impl Options {
+    /// Parses `args`, accepting the `valued` options followed by a value
+    /// and the `switches` standing alone.
+    fn parse(args: &[String], valued: &[&str], switches: &[&str]) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Unexpected argument {}", arg))?;
+            if valued.contains(&name) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("--{} needs a value", name))?;
+                values.insert(name.to_string(), value.clone());
+            } else if switches.contains(&name) {
+                values.insert(name.to_string(), String::new());
+            } else {
+                return Err(format!("Unknown option --{}", name));
+            }
+        }
+        Ok(Self { values })
+    }
+
+    fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+}
+
+/// Runs the command line interface and returns the process exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let Some((command, rest)) = args.split_first() else {
+        eprint!("{}", USAGE);
+        return 2;
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Could not start: {}", e);
+            return 1;
+        }
+    };
+    let result = runtime.block_on(async {
+        match command.as_str() {
+            "login" => login(Options::parse(rest, &[], &["modify", "filters", "manual"])?).await,
+            "logout" => logout(),
+            "scan" => scan(Options::parse(rest, &["query", "max"], &["json"])?).await,
+            "report" => report(Options::parse(rest, &[], &["json"])?).await,
+            "export" => export(Options::parse(rest, &["format", "output"], &[])?).await,
+            "serve" => {
+                Options::parse(rest, &[], &[])?;
+                serve()
+            }
+            "help" | "--help" | "-h" => {
+                print!("{}", USAGE);
+                Ok(())
+            }
+            command => Err(format!("Unknown command {}\n\n{}", command, USAGE)),
+        }
+    });
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+/// Runs the web server the GUI front ends start, without a window.
+fn serve() -> Result<(), String> {
+    let mut app = AppState::new();
+    app.start();
+    println!("Open http://127.0.0.1:5000 in your web browser, press Ctrl+C to stop");
+    loop {
+        std::thread::park();
+    }
+}
+
+fn credentials() -> Result<Credentials, String> {
+    let credentials_file = include_str!("../credentials.json");
+    serde_json::from_str::<Credentials>(credentials_file)
+        .map_err(|e| format!("Could not read the OAuth credentials: {}", e))
+}
+
+fn token_path() -> Result<std::path::PathBuf, String> {
+    app_data_dir()
+        .map(|dir| dir.join("cli_token.json"))
+        .ok_or_else(|| "Could not find the app data directory".to_string())
+}
+
+fn save_token(token: &SavedToken) -> Result<(), String> {
+    let path = token_path()?;
+    let data = serde_json::to_string_pretty(token).map_err(|e| e.to_string())?;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // The token grants access to the mailbox, keep it private to the user.
+    // The mode only applies to new files, so existing ones are restricted
+    // before the token is written
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(&path)
+        .and_then(|mut file| {
+            #[cfg(unix)]
+            file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+            file.write_all(data.as_bytes())
+        })
+        .map_err(|e| format!("Could not save the token to {}: {}", path.display(), e))
+}
+
+/// Exchanges an authorization code or a refresh token at the token
+/// endpoint.
+async fn request_token(
+    credentials: &Credentials,
+    params: &[(&str, &str)],
+) -> Result<TokenResponse, String> {
+    let mut form = vec![
+        ("client_id", credentials.web.client_id.as_str()),
+        ("client_secret", credentials.web.client_secret.as_str()),
+    ];
+    form.extend_from_slice(params);
+    let response = reqwest::Client::new()
+        .post(&credentials.web.token_uri)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Could not reach Google: {}", e))?;
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Could not read the token: {}", e))?;
+    serde_json::from_str::<TokenResponse>(&text).map_err(|_| format!("Sign in failed: {}", text))
+}
+
+/// Returns the saved access token, refreshing it if it has expired.
+async fn access_token() -> Result<String, String> {
+    let path = token_path()?;
+    let mut token: SavedToken = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .ok_or_else(|| "Not signed in, run `gmail-cleaner-rs login` first".to_string())?;
+    if now_millis() < token.expires_at - 60_000 {
+        return Ok(token.access_token);
+    }
+    let refresh_token = token
+        .refresh_token
+        .clone()
+        .ok_or_else(|| "The sign in has expired, run `gmail-cleaner-rs login` again".to_string())?;
+    let credentials = credentials()?;
+    let response = request_token(
+        &credentials,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ],
+    )
+    .await?;
+    token.access_token = response.access_token;
+    token.expires_at = now_millis() + response.expires_in as i64 * 1000;
+    save_token(&token)?;
+    Ok(token.access_token)
+}
+
+/// Pulls the authorization code out of the address Google redirected to,
+/// which has to carry the `state` the sign in was started with.
+fn code_from_redirect(redirect: &str, state: &str) -> Result<String, String> {
+    let url = Url::parse(redirect)
+        .or_else(|_| Url::parse(&format!("http://localhost{}", redirect)))
+        .map_err(|_| format!("Could not read the address {}", redirect))?;
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    if params.get("state").map(String::as_str) != Some(state) {
+        return Err("The address does not belong to this sign in".to_string());
+    }
+    if let Some(error) = params.get("error") {
+        return Err(format!("Sign in failed: {}", error));
+    }
+    params
+        .get("code")
+        .cloned()
+        .ok_or_else(|| "The address has no authorization code".to_string())
+}
+
+/// Waits for the browser to be redirected back to the loopback address
+/// and returns the authorization code. Requests without the `state` of
+/// this sign in, which other programs or web pages could send, are
+/// turned away.
+fn wait_for_code(redirect_uri: &str, state: &str) -> Result<String, String> {
+    let url = Url::parse(redirect_uri).map_err(|e| e.to_string())?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| {
+        format!(
+            "Could not listen on port {}, is the app already running? ({})",
+            port, e
+        )
+    })?;
+    for stream in listener.incoming() {
+        let mut stream = stream.map_err(|e| e.to_string())?;
+        // A connection which never sends its request must not keep the
+        // browser's redirect from being read
+        let mut request_line = String::new();
+        let read = stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .and_then(|_| BufReader::new(&stream).read_line(&mut request_line));
+        if read.is_err() {
+            continue;
+        }
+        let path = request_line.split(' ').nth(1).unwrap_or_default();
+        // Browsers also ask for things like the favicon
+        if !path.starts_with(url.path()) {
+            let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+            continue;
+        }
+        let url = Url::parse(&format!("http://localhost{}", path)).map_err(|e| e.to_string())?;
+        let echoed = url
+            .query_pairs()
+            .any(|(name, value)| name == "state" && value == state);
+        if !echoed {
+            let _ = stream.write_all(
+                b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            );
+            continue;
+        }
+        let result = code_from_redirect(path, state);
+        let body = match &result {
+            Ok(_) => "Signed in to Gmail Cleaner. You can close this window.",
+            Err(_) => "Signing in to Gmail Cleaner failed, see the terminal.",
+        };
+        let _ = stream.write_all(
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .as_bytes(),
+        );
+        return result;
+    }
+    Err("Stopped waiting for the sign in".to_string())
+}
+
+async fn login(options: Options) -> Result<(), String> {
+    let credentials = credentials()?;
+    let redirect_uri = credentials
+        .web
+        .redirect_uris
+        .first()
+        .cloned()
+        .ok_or_else(|| "The credentials do not list any redirect URI".to_string())?;
+    let scope = if options.has("filters") {
+        format!("{} {} {}", READONLY_SCOPE, MODIFY_SCOPE, SETTINGS_SCOPE)
+    } else if options.has("modify") {
+        format!("{} {}", READONLY_SCOPE, MODIFY_SCOPE)
+    } else {
+        READONLY_SCOPE.to_string()
+    };
+    // The state ties the redirect to this sign in, and PKCE the code to
+    // this process
+    let state = random_string(16)?;
+    let code_verifier = random_string(32)?;
+    let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+    // Offline access gives a refresh token, so scripts keep working after
+    // the access token expires
+    let auth_url = format!(
+        "https://accounts.google.com/o/oauth2/v2/auth?scope={}&redirect_uri={}&response_type=code&client_id={}&access_type=offline&prompt=consent&state={}&code_challenge={}&code_challenge_method=S256",
+        urlencoding::encode(&scope),
+        urlencoding::encode(&redirect_uri),
+        credentials.web.client_id,
+        state,
+        code_challenge
+    );
+
+    println!("Open this address to sign in:\n\n{}\n", auth_url);
+    let code = if options.has("manual") {
+        println!("Then paste the address the browser ends up on, even if the page does not load:");
+        let mut redirect = String::new();
+        std::io::stdin()
+            .read_line(&mut redirect)
+            .map_err(|e| e.to_string())?;
+        code_from_redirect(redirect.trim(), &state)?
+    } else {
+        let _ = open::that(&auth_url);
+        println!("Waiting for the browser...");
+        wait_for_code(&redirect_uri, &state)?
+    };
+
+    let response = request_token(
+        &credentials,
+        &[
+            ("code", &code),
+            ("code_verifier", &code_verifier),
+            ("grant_type", "authorization_code"),
+            ("redirect_uri", &redirect_uri),
+        ],
+    )
+    .await?;
+    let token = SavedToken {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token,
+        expires_at: now_millis() + response.expires_in as i64 * 1000,
+        scope: response.scope,
+    };
+    let profile = profile_get(&token.access_token)
+        .await
+        .map_err(|_| "Could not load the profile".to_string())?;
+    save_token(&token)?;
+    println!("Signed in as {}", profile.emailAddress);
+    Ok(())
+}
+
+fn logout() -> Result<(), String> {
+    let path = token_path()?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+    }
+    println!("Signed out");
+    Ok(())
+}
+
+/// Returns the access token and the email address of the signed in account.
+async fn account() -> Result<(String, String), String> {
+    let token = access_token().await?;
+    let profile = profile_get(&token)
+        .await
+        .map_err(|_| "Could not load the profile".to_string())?;
+    Ok((token, profile.emailAddress))
+}
+
+async fn label_names(token: &str) -> HashMap<String, String> {
+    labels_list(token)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|label| (label.id, label.name))
+        .collect()
+}
+
+/// Shortens `text` to at most `width` characters for a table column.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut short: String = text.chars().take(width - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+async fn scan(options: Options) -> Result<(), String> {
+    let max: usize = match options.get("max") {
+        Some(max) => max
+            .parse()
+            .map_err(|_| format!("--max must be a number, not {}", max))?,
+        None => 100,
+    };
+    let (token, account) = account().await?;
+    let cache = MessageCache::open();
+    let classifier = SettingsStore::load().size_classifier();
+
+    let mut messages: Vec<CachedMessage> = vec![];
+    let mut fetched = 0;
+    let mut page_token: Option<String> = None;
+    while messages.len() < max {
+        let page_size = (max - messages.len()).min(500) as u32;
+        let list = messages_list(
+            &token,
+            page_size,
+            page_token.as_deref(),
+            options.get("query"),
+        )
+        .await
+        .map_err(|_| "Could not list the messages".to_string())?;
+        for entry in list.messages {
+            if let Some(cached) = cache.get(&account, &entry.id) {
+                messages.push(cached);
+            } else if let Ok(msg) = message_get(&token, &entry.id).await {
+                let cached = CachedMessage::from_message(msg);
+                cache.put(&account, &cached);
+                messages.push(cached);
+                fetched += 1;
+                if fetched % 50 == 0 {
+                    eprintln!("Fetched {} messages", fetched);
+                }
+            }
+        }
+        match list.nextPageToken {
+            Some(next_token) => page_token = Some(next_token),
+            None => break,
+        }
+    }
+
+    if options.has("json") {
+        let names = label_names(&token).await;
+        let rows: Vec<ExportRow> = messages
+            .iter()
+            .map(|m| ExportRow::new(m, &classifier, &names))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+    println!(
+        "{:>10}  {:<8}  {:<10}  {:<30}  SUBJECT",
+        "SIZE", "BUCKET", "DATE", "SENDER"
+    );
+    for message in &messages {
+        println!(
+            "{:>10}  {:<8}  {:<10}  {:<30}  {}",
+            format_size(message.size_estimate as i64),
+            truncate(classifier.classify(message.size_estimate as i64), 8),
+            &iso_date(message.date)[..10],
+            truncate(&message.sender, 30),
+            truncate(&message.subject, 60)
+        );
+    }
+    println!(
+        "\n{} messages, {}",
+        messages.len(),
+        format_size(messages.iter().map(|m| m.size_estimate as i64).sum())
+    );
+    Ok(())
+}
+
+fn print_rows(title: &str, rows: &[ReportRow]) {
+    println!("\n{}", title);
+    for row in rows {
+        println!(
+            "  {:<40}  {:>7} messages  {:>10}  {:>6}",
+            truncate(&row.name, 40),
+            row.message_count,
+            row.size,
+            row.share
+        );
+    }
+}
+
+async fn report(options: Options) -> Result<(), String> {
+    let (token, account) = account().await?;
+    let cache = MessageCache::open();
+    let classifier = SettingsStore::load().size_classifier();
+    let report = StorageReport::new(
+        &account,
+        &cache.all(&account),
+        &classifier,
+        &label_names(&token).await,
+        now_millis(),
+    );
+    if options.has("json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+    println!(
+        "Storage report for {}, {}",
+        report.account, report.generated_at
+    );
+    println!(
+        "{} messages scanned, {} in total",
+        report.message_count, report.total_size
+    );
+    print_rows("By size bucket", &report.buckets);
+    print_rows("Top senders", &report.senders);
+    print_rows("By label", &report.labels);
+    print_rows("By year received", &report.ages);
+    println!("\nLargest messages");
+    for message in &report.largest {
+        println!(
+            "  {:>10}  {:<10}  {:<30}  {}",
+            message.size,
+            message.date,
+            truncate(&message.sender, 30),
+            truncate(&message.subject, 60)
+        );
+    }
+    Ok(())
+}
+
+async fn export(options: Options) -> Result<(), String> {
+    let format = options.get("format").unwrap_or("csv");
+    let (token, account) = account().await?;
+    let cache = MessageCache::open();
+    let classifier = SettingsStore::load().size_classifier();
+    let names = label_names(&token).await;
+    let mut messages = cache.all(&account);
+    messages.sort_by_key(|message| Reverse(message.date));
+    let rows: Vec<ExportRow> = messages
+        .iter()
+        .map(|m| ExportRow::new(m, &classifier, &names))
+        .collect();
+    let data = match format {
+        "csv" => to_csv(&rows),
+        "ndjson" => to_ndjson(&rows),
+        "json" => serde_json::to_string_pretty(&rows).map_err(|e| e.to_string())?,
+        format => return Err(format!("Unknown export format {}", format)),
+    };
+    match options.get("output") {
+        Some(path) => {
+            std::fs::write(path, data).map_err(|e| format!("Could not write {}: {}", path, e))?;
+            eprintln!("Exported {} messages to {}", rows.len(), path);
+        }
+        None => print!("{}", data),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_code_of_this_sign_in() {
+        let state = random_string(16).unwrap();
+        let redirect = format!("/oauth2callback?state={}&code=4%2F0Ab&scope=x", state);
+        assert_eq!(code_from_redirect(&redirect, &state).unwrap(), "4/0Ab");
+        let redirect = format!(
+            "http://localhost:5000/oauth2callback?code=abc&state={}",
+            state
+        );
+        assert_eq!(code_from_redirect(&redirect, &state).unwrap(), "abc");
+    }
+
+    #[test]
+    fn refuses_codes_of_other_sign_ins() {
+        let state = random_string(16).unwrap();
+        for redirect in [
+            "/oauth2callback?code=attacker".to_string(),
+            "/oauth2callback?code=attacker&state=".to_string(),
+            format!("/oauth2callback?code=attacker&state={}x", state),
+        ] {
+            assert!(
+                code_from_redirect(&redirect, &state).is_err(),
+                "{}",
+                redirect
+            );
+        }
+        assert_ne!(random_string(16).unwrap(), state);
+        assert_eq!(random_string(32).unwrap().len(), 43);
+    }
+}
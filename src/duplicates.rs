@@ -0,0 +1,152 @@
+use crate::attachments::{attachment_content, Attachment};
+use crate::cache::CachedMessage;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// One copy of a duplicated attachment.
+#[derive(Serialize, Clone)]
+pub struct DuplicateCopy {
+    pub message_id: String,
+    pub thread_id: String,
+    pub subject: String,
+    pub part_id: String,
+    pub attachment_id: Option<String>,
+}
+
+/// Attachments which are probably the same file, since they share a
+/// filename, MIME type and size. Once `verified`, every copy is known to
+/// have the same content.
+#[derive(Serialize)]
+pub struct DuplicateGroup {
+    pub filename: String,
+    pub mime_type: String,
+    pub size: i64,
+    pub verified: bool,
+    pub copies: Vec<DuplicateCopy>,
+    /// Space taken by all the copies but one.
+    pub redundant_size: i64,
+}
+
+#[derive(Serialize)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub redundant_size: i64,
+}
+
+impl DuplicateReport {
+    fn new(mut groups: Vec<DuplicateGroup>) -> Self {
+        groups.sort_by_key(|group| Reverse(group.redundant_size));
+        Self {
+            redundant_size: groups.iter().map(|g| g.redundant_size).sum(),
+            groups,
+        }
+    }
+}
+
+fn group(
+    filename: String,
+    mime_type: String,
+    size: i64,
+    verified: bool,
+    copies: Vec<DuplicateCopy>,
+) -> DuplicateGroup {
+    DuplicateGroup {
+        redundant_size: size * (copies.len() as i64 - 1),
+        filename,
+        mime_type,
+        size,
+        verified,
+        copies,
+    }
+}
+
+/// Groups the attachments of all the messages by filename, MIME type and
+/// size, keeping the groups with more than one copy.
+pub fn probable_duplicates(messages: &[CachedMessage]) -> DuplicateReport {
+    let mut groups: HashMap<(String, String, i64), Vec<DuplicateCopy>> = HashMap::new();
+    for message in messages {
+        for attachment in &message.attachments {
+            if attachment.size == 0 {
+                continue;
+            }
+            let key = (
+                attachment.filename.to_lowercase(),
+                attachment.mime_type.clone(),
+                attachment.size,
+            );
+            groups.entry(key).or_default().push(DuplicateCopy {
+                message_id: message.id.clone(),
+                thread_id: message.thread_id.clone(),
+                subject: message.subject.clone(),
+                part_id: attachment.part_id.clone(),
+                attachment_id: attachment.attachment_id.clone(),
+            });
+        }
+    }
+    DuplicateReport::new(
+        groups
+            .into_iter()
+            .filter(|(_, copies)| copies.len() > 1)
+            .map(|((filename, mime_type, size), copies)| {
+                group(filename, mime_type, size, false, copies)
+            })
+            .collect(),
+    )
+}
+
+/// Downloads every copy of the first `limit` groups and splits them by a
+/// SHA-256 hash of their content, so that only identical files remain
+/// grouped. Groups with a copy which could not be downloaded, and groups
+/// beyond `limit`, are kept unverified.
+pub async fn verify_duplicates(
+    token: &str,
+    report: DuplicateReport,
+    limit: usize,
+) -> DuplicateReport {
+    let mut groups = Vec::new();
+    for (index, probable) in report.groups.into_iter().enumerate() {
+        if index >= limit {
+            groups.push(probable);
+            continue;
+        }
+        let mut by_hash: HashMap<Vec<u8>, Vec<DuplicateCopy>> = HashMap::new();
+        let mut complete = true;
+        for copy in &probable.copies {
+            let attachment = Attachment {
+                part_id: copy.part_id.clone(),
+                filename: probable.filename.clone(),
+                mime_type: probable.mime_type.clone(),
+                size: probable.size,
+                attachment_id: copy.attachment_id.clone(),
+            };
+            match attachment_content(token, &copy.message_id, &attachment).await {
+                Ok(content) => {
+                    let hash = Sha256::digest(&content).to_vec();
+                    by_hash.entry(hash).or_default().push(copy.clone());
+                }
+                Err(()) => {
+                    complete = false;
+                    break;
+                }
+            }
+        }
+        if !complete {
+            groups.push(probable);
+            continue;
+        }
+        for copies in by_hash.into_values() {
+            if copies.len() > 1 {
+                groups.push(group(
+                    probable.filename.clone(),
+                    probable.mime_type.clone(),
+                    probable.size,
+                    true,
+                    copies,
+                ));
+            }
+        }
+    }
+    DuplicateReport::new(groups)
+}
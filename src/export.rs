@@ -0,0 +1,212 @@
+use crate::cache::CachedMessage;
+use crate::classifier::SizeClassifier;
+use crate::stats::iso_date;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One scanned message as exported for spreadsheets and scripts.
+#[derive(Serialize)]
+pub struct ExportRow {
+    pub id: String,
+    pub thread_id: String,
+    pub subject: String,
+    pub sender: String,
+    /// UTC date received, e.g. `2024-05-01T09:30:00Z`.
+    pub date: String,
+    /// Label names, or IDs for labels whose name is unknown.
+    pub labels: Vec<String>,
+    pub size: i32,
+    pub bucket: String,
+}
+
+impl ExportRow {
+    pub fn new(
+        message: &CachedMessage,
+        classifier: &SizeClassifier,
+        label_names: &HashMap<String, String>,
+    ) -> Self {
+        Self {
+            id: message.id.clone(),
+            thread_id: message.thread_id.clone(),
+            subject: message.subject.clone(),
+            sender: message.sender.clone(),
+            date: iso_date(message.date),
+            labels: message
+                .labels
+                .iter()
+                .map(|id| label_names.get(id).cloned().unwrap_or_else(|| id.clone()))
+                .collect(),
+            size: message.size_estimate,
+            bucket: classifier
+                .classify(message.size_estimate as i64)
+                .to_string(),
+        }
+    }
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break.
+/// Subjects and senders come from the mail itself, so a field which a
+/// spreadsheet would run as a formula is prefixed with an apostrophe.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+/// Formats the rows as CSV with a header line. Labels are joined with
+/// semicolons.
+pub fn to_csv(rows: &[ExportRow]) -> String {
+    let mut csv = String::from("id,thread_id,subject,sender,date,labels,size,bucket\r\n");
+    for row in rows {
+        let fields = [
+            csv_field(&row.id),
+            csv_field(&row.thread_id),
+            csv_field(&row.subject),
+            csv_field(&row.sender),
+            csv_field(&row.date),
+            csv_field(&row.labels.join(";")),
+            row.size.to_string(),
+            csv_field(&row.bucket),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Formats the rows as newline delimited JSON, one object per line.
+pub fn to_ndjson(rows: &[ExportRow]) -> String {
+    rows.iter()
+        .filter_map(|row| serde_json::to_string(row).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(subject: &str, sender: &str, date: i64, labels: &[&str]) -> ExportRow {
+        let message = CachedMessage {
+            id: "18c1".to_string(),
+            thread_id: "18c0".to_string(),
+            subject: subject.to_string(),
+            sender: sender.to_string(),
+            date,
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            size_estimate: 2048,
+            attachments: vec![],
+            list_id: String::new(),
+            list_unsubscribe: String::new(),
+            list_unsubscribe_post: String::new(),
+            precedence: String::new(),
+        };
+        let label_names = HashMap::from([("Label_1".to_string(), "Receipts".to_string())]);
+        ExportRow::new(&message, &SizeClassifier::default(), &label_names)
+    }
+
+    fn csv_lines(rows: &[ExportRow]) -> Vec<String> {
+        to_csv(rows).split("\r\n").map(str::to_string).collect()
+    }
+
+    #[test]
+    fn writes_plain_fields_as_they_are() {
+        let lines = csv_lines(&[row("Invoice", "shop@example.com", 0, &["INBOX"])]);
+        assert_eq!(
+            lines,
+            [
+                "id,thread_id,subject,sender,date,labels,size,bucket",
+                "18c1,18c0,Invoice,shop@example.com,1970-01-01T00:00:00Z,INBOX,2048,small",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_separators_quotes_and_line_breaks() {
+        let csv = to_csv(&[row(
+            "Re: \"Lunch\", maybe\r\ntomorrow",
+            "Ann <ann@example.com>",
+            0,
+            &[],
+        )]);
+        assert!(csv.contains(",\"Re: \"\"Lunch\"\", maybe\r\ntomorrow\",Ann <ann@example.com>,"));
+    }
+
+    #[test]
+    fn joins_labels_by_name() {
+        let lines = csv_lines(&[row(
+            "Receipt",
+            "shop@example.com",
+            0,
+            &["INBOX", "Label_1", "Label_2"],
+        )]);
+        assert!(lines[1].contains(",INBOX;Receipts;Label_2,"));
+    }
+
+    #[test]
+    fn defuses_formulas() {
+        for (subject, field) in [
+            (
+                "=HYPERLINK(\"http://evil.example\",\"x\")",
+                "\"'=HYPERLINK(\"\"http://evil.example\"\",\"\"x\"\")\"",
+            ),
+            ("+1", "'+1"),
+            ("-1", "'-1"),
+            ("@SUM(A1)", "'@SUM(A1)"),
+            ("\tcmd", "'\tcmd"),
+            ("\rcmd", "\"'\rcmd\""),
+            ("Total = 5", "Total = 5"),
+        ] {
+            let csv = to_csv(&[row(subject, "shop@example.com", 0, &[])]);
+            assert!(
+                csv.contains(&format!(",{},shop@example.com,", field)),
+                "{}",
+                csv
+            );
+        }
+        let csv = to_csv(&[row("Hi", "=cmd|' /C calc'!A0", 0, &[])]);
+        assert!(csv.contains(",Hi,'=cmd|' /C calc'!A0,"));
+    }
+
+    #[test]
+    fn formats_dates_around_the_epoch() {
+        assert_eq!(row("", "", 0, &[]).date, "1970-01-01T00:00:00Z");
+        assert_eq!(row("", "", -1, &[]).date, "1969-12-31T23:59:59Z");
+        assert_eq!(
+            row("", "", -86_400_000 * 365, &[]).date,
+            "1969-01-01T00:00:00Z"
+        );
+        assert_eq!(
+            row("", "", 951_782_400_000, &[]).date,
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            row("", "", 1_714_555_800_000, &[]).date,
+            "2024-05-01T09:30:00Z"
+        );
+    }
+
+    #[test]
+    fn writes_one_json_object_per_line() {
+        let ndjson = to_ndjson(&[
+            row("First\nline", "a@example.com", 0, &["Label_1"]),
+            row("Second", "b@example.com", 0, &[]),
+        ]);
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(ndjson.ends_with('\n'));
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["subject"], "First\nline");
+        assert_eq!(first["labels"], serde_json::json!(["Receipts"]));
+        assert_eq!(first["size"], 2048);
+        assert_eq!(to_ndjson(&[]), "");
+    }
+}
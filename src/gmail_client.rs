@@ -1,3 +1,7 @@
+use base64::alphabet::URL_SAFE;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
 use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -21,7 +25,7 @@ pub struct MessageHeader {
     pub value: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default)]
 pub struct MessagePartBody {
     pub size: i32,
     pub data: Option<String>,
@@ -30,10 +34,15 @@ pub struct MessagePartBody {
 
 #[derive(Deserialize)]
 pub struct MessagePart {
+    #[serde(default)]
     pub partId: String,
     pub mimeType: String,
+    #[serde(default)]
     pub filename: String,
+    #[serde(default)]
     pub headers: Vec<MessageHeader>,
+    // Not included in metadata format responses
+    #[serde(default)]
     pub body: MessagePartBody,
     pub parts: Option<Vec<MessagePart>>,
 }
@@ -42,6 +51,7 @@ pub struct MessagePart {
 pub struct Message {
     pub id: String,
     pub threadId: String,
+    #[serde(default)]
     pub labelIds: Vec<String>,
     pub snippet: String,
     pub historyId: String,
@@ -59,11 +69,99 @@ pub struct MessageListEntry {
 
 #[derive(Deserialize)]
 pub struct MessagesList {
+    #[serde(default)]
     pub messages: Vec<MessageListEntry>,
     pub nextPageToken: Option<String>,
     pub resultSizeEstimate: Option<i32>,
 }
 
+#[derive(Deserialize)]
+pub struct Thread {
+    pub id: String,
+    pub historyId: String,
+    pub messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+pub struct Label {
+    pub id: String,
+    pub name: String,
+    /// Either "system" or "user".
+    #[serde(rename = "type")]
+    pub labelType: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LabelsList {
+    #[serde(default)]
+    pub labels: Vec<Label>,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryMessageChange {
+    pub message: MessageListEntry,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryLabelChange {
+    pub message: MessageListEntry,
+    pub labelIds: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct History {
+    pub id: String,
+    #[serde(default)]
+    pub messagesAdded: Vec<HistoryMessageChange>,
+    #[serde(default)]
+    pub messagesDeleted: Vec<HistoryMessageChange>,
+    #[serde(default)]
+    pub labelsAdded: Vec<HistoryLabelChange>,
+    #[serde(default)]
+    pub labelsRemoved: Vec<HistoryLabelChange>,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryList {
+    #[serde(default)]
+    pub history: Vec<History>,
+    pub nextPageToken: Option<String>,
+    pub historyId: String,
+}
+
+/// What a filter matches; at least one field must be set.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct FilterCriteria {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+}
+
+/// What a filter does to the messages it matches.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct FilterAction {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addLabelIds: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removeLabelIds: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Filter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub criteria: FilterCriteria,
+    pub action: FilterAction,
+}
+
+pub enum HistoryError {
+    /// The start history ID is too old for Gmail to list changes from it,
+    /// so a full scan is needed.
+    Expired,
+    Failed,
+}
+
 /// Retrieves a list of messages from the user's Gmail account
 /// by calling the users.messages.list method of the Gmail API.
 ///
@@ -71,6 +169,7 @@ pub struct MessagesList {
 /// * `token` - The access token for Gmail API authentication.
 /// * `max_results` - The maximum number of messages to return.
 /// * `page_token` - Optional token for pagination.
+/// * `query` - Optional Gmail search query, e.g. `larger:10M`.
 ///
 /// # Returns
 /// A Result containing MessagesList on success or an error on failure.
@@ -78,6 +177,7 @@ pub async fn messages_list(
     token: &str,
     max_results: u32,
     page_token: Option<&str>,
+    query: Option<&str>,
 ) -> Result<MessagesList, ()> {
     let client = reqwest::Client::new();
     let mut request = client
@@ -89,6 +189,10 @@ pub async fn messages_list(
         request = request.query(&[("pageToken", token)]);
     }
 
+    if let Some(q) = query {
+        request = request.query(&[("q", q)]);
+    }
+
     let result = request.send().await;
     match result {
         Ok(response) => match response.json::<MessagesList>().await {
@@ -107,6 +211,8 @@ pub async fn messages_list(
 
 /// Retrieves a specific message from the user's Gmail account
 /// by calling the users.messages.get method of the Gmail API.
+/// The full format is used, which carries the MIME tree as well as
+/// all the headers, including the mailing list ones.
 ///
 /// # Arguments
 /// * `token` - The access token for Gmail API authentication.
@@ -140,23 +246,628 @@ pub async fn message_get(token: &str, id: &str) -> Result<Message, ()> {
     }
 }
 
-pub async fn user_get(token: &str) -> Result<User, ()> {
+/// Retrieves a message as the RFC 2822 text it was received as
+/// by calling the users.messages.get method of the Gmail API
+/// with the raw format.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the message to retrieve.
+///
+/// # Returns
+/// A Result containing Message, with `raw` set and no payload, on success
+/// or an error on failure.
+pub async fn message_get_raw(token: &str, id: &str) -> Result<Message, ()> {
     let client = reqwest::Client::new();
     let result = client
-        .get("https://gmail.googleapis.com/gmail/v1/users/me/profile")
+        .get(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}?format=raw",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+    match result {
+        Ok(response) => match response.json::<Message>().await {
+            Ok(message) => Ok(message),
+            Err(e) => {
+                println!("json parsing error: {}", e);
+                Err(())
+            }
+        },
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Retrieves a thread with the headers of all its messages
+/// by calling the users.threads.get method of the Gmail API
+/// with the metadata format.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the thread to retrieve.
+///
+/// # Returns
+/// A Result containing Thread on success or an error on failure.
+pub async fn threads_get(token: &str, id: &str) -> Result<Thread, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .get(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/threads/{}",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .query(&[
+            ("format", "metadata"),
+            ("metadataHeaders", "Subject"),
+            ("metadataHeaders", "From"),
+        ])
+        .send()
+        .await;
+    match result {
+        Ok(response) => match response.json::<Thread>().await {
+            Ok(thread) => Ok(thread),
+            Err(e) => {
+                println!("json parsing error: {}", e);
+                Err(())
+            }
+        },
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Retrieves the labels of the user's Gmail account
+/// by calling the users.labels.list method of the Gmail API.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+///
+/// # Returns
+/// A Result containing the labels on success or an error on failure.
+pub async fn labels_list(token: &str) -> Result<Vec<Label>, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/labels")
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await;
     match result {
+        Ok(response) => match response.json::<LabelsList>().await {
+            Ok(list) => Ok(list.labels),
+            Err(e) => {
+                println!("json parsing error: {}", e);
+                Err(())
+            }
+        },
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Creates a user label
+/// by calling the users.labels.create method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `name` - The display name of the label.
+///
+/// # Returns
+/// A Result containing the new Label on success or an error on failure.
+pub async fn labels_create(token: &str, name: &str) -> Result<Label, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post("https://gmail.googleapis.com/gmail/v1/users/me/labels")
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "name": name,
+            "labelListVisibility": "labelShow",
+            "messageListVisibility": "show",
+        }))
+        .send()
+        .await;
+    match result {
+        Ok(response) => match response.json::<Label>().await {
+            Ok(label) => Ok(label),
+            Err(e) => {
+                println!("json parsing error: {}", e);
+                Err(())
+            }
+        },
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Creates a filter applied to incoming mail
+/// by calling the users.settings.filters.create method of the Gmail API.
+/// Needs the gmail.settings.basic scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `filter` - The criteria and action of the filter.
+///
+/// # Returns
+/// A Result containing the created Filter on success or an error on failure.
+pub async fn filters_create(token: &str, filter: &Filter) -> Result<Filter, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post("https://gmail.googleapis.com/gmail/v1/users/me/settings/filters")
+        .header("Authorization", format!("Bearer {}", token))
+        .json(filter)
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => match response.json::<Filter>().await {
+            Ok(filter) => Ok(filter),
+            Err(e) => {
+                println!("json parsing error: {}", e);
+                Err(())
+            }
+        },
         Ok(response) => {
-            if let Ok(profile) = response.json::<Profile>().await {
-                Ok(User {
-                    email: profile.emailAddress,
-                })
-            } else {
+            println!("filter error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Decodes the base64url encoded data Gmail returns for attachments and
+/// raw messages, which may or may not be padded.
+pub fn decode_base64url(data: &str) -> Result<Vec<u8>, ()> {
+    let engine = GeneralPurpose::new(
+        &URL_SAFE,
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    );
+    engine.decode(data).map_err(|e| {
+        println!("base64 decoding error: {}", e);
+    })
+}
+
+/// Encodes data the way Gmail expects raw messages, base64url with padding.
+pub fn encode_base64url(data: &[u8]) -> String {
+    GeneralPurpose::new(&URL_SAFE, GeneralPurposeConfig::new()).encode(data)
+}
+
+/// Retrieves the content of an attachment
+/// by calling the users.messages.attachments.get method of the Gmail API.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `message_id` - The ID of the message the attachment belongs to.
+/// * `attachment_id` - The ID of the attachment.
+///
+/// # Returns
+/// A Result containing the decoded attachment bytes on success or an error on failure.
+pub async fn attachment_get(
+    token: &str,
+    message_id: &str,
+    attachment_id: &str,
+) -> Result<Vec<u8>, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .get(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}/attachments/{}",
+            message_id, attachment_id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+    match result {
+        Ok(response) => match response.json::<MessagePartBody>().await {
+            Ok(body) => decode_base64url(body.data.as_deref().unwrap_or_default()),
+            Err(e) => {
+                println!("json parsing error: {}", e);
                 Err(())
             }
+        },
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Adds a message to the mailbox without sending it
+/// by calling the users.messages.insert method of the Gmail API.
+/// The internal date is taken from the Date header of the message.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `raw` - The RFC 2822 text of the message.
+/// * `label_ids` - The labels to put on the message.
+/// * `thread_id` - The thread to add the message to.
+///
+/// # Returns
+/// A Result containing the ID and thread ID of the new message on success
+/// or an error on failure.
+pub async fn messages_insert(
+    token: &str,
+    raw: &[u8],
+    label_ids: &[String],
+    thread_id: &str,
+) -> Result<MessageListEntry, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages?internalDateSource=dateHeader",
+        )
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "raw": encode_base64url(raw),
+            "labelIds": label_ids,
+            "threadId": thread_id,
+        }))
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => {
+            match response.json::<MessageListEntry>().await {
+                Ok(entry) => Ok(entry),
+                Err(e) => {
+                    println!("json parsing error: {}", e);
+                    Err(())
+                }
+            }
+        }
+        Ok(response) => {
+            println!("insert error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Moves a message to the Trash
+/// by calling the users.messages.trash method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the message to trash.
+///
+/// # Returns
+/// A Result which is Ok if the message was trashed or an error on failure.
+pub async fn messages_trash(token: &str, id: &str) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}/trash",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Length", "0")
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("trash error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Moves all the messages of a thread to the Trash
+/// by calling the users.threads.trash method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the thread to trash.
+///
+/// # Returns
+/// A Result which is Ok if the thread was trashed or an error on failure.
+pub async fn threads_trash(token: &str, id: &str) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/threads/{}/trash",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Length", "0")
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("trash error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Takes a message out of the Trash
+/// by calling the users.messages.untrash method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the message to restore.
+///
+/// # Returns
+/// A Result which is Ok if the message was restored or an error on failure.
+pub async fn messages_untrash(token: &str, id: &str) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}/untrash",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Length", "0")
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("untrash error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Takes all the messages of a thread out of the Trash
+/// by calling the users.threads.untrash method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the thread to restore.
+///
+/// # Returns
+/// A Result which is Ok if the thread was restored or an error on failure.
+pub async fn threads_untrash(token: &str, id: &str) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/threads/{}/untrash",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Length", "0")
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("untrash error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Adds and removes labels on a message
+/// by calling the users.messages.modify method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the message to modify.
+/// * `add_label_ids` - The labels to add to the message.
+/// * `remove_label_ids` - The labels to remove from the message.
+///
+/// # Returns
+/// A Result which is Ok if the message was modified or an error on failure.
+pub async fn messages_modify(
+    token: &str,
+    id: &str,
+    add_label_ids: &[&str],
+    remove_label_ids: &[&str],
+) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/messages/{}/modify",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "addLabelIds": add_label_ids,
+            "removeLabelIds": remove_label_ids,
+        }))
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("modify error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Adds and removes labels on all the messages of a thread
+/// by calling the users.threads.modify method of the Gmail API.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `id` - The ID of the thread to modify.
+/// * `add_label_ids` - The labels to add to the messages.
+/// * `remove_label_ids` - The labels to remove from the messages.
+///
+/// # Returns
+/// A Result which is Ok if the thread was modified or an error on failure.
+pub async fn threads_modify(
+    token: &str,
+    id: &str,
+    add_label_ids: &[&str],
+    remove_label_ids: &[&str],
+) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post(format!(
+            "https://gmail.googleapis.com/gmail/v1/users/me/threads/{}/modify",
+            id
+        ))
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "addLabelIds": add_label_ids,
+            "removeLabelIds": remove_label_ids,
+        }))
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("modify error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// The most message IDs users.messages.batchModify accepts in one call.
+pub const BATCH_MODIFY_LIMIT: usize = 1000;
+
+/// Adds and removes labels on many messages at once
+/// by calling the users.messages.batchModify method of the Gmail API.
+/// Adding the TRASH label moves the messages to the Trash.
+/// Needs the gmail.modify scope.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `ids` - The IDs of the messages, at most `BATCH_MODIFY_LIMIT`.
+/// * `add_label_ids` - The labels to add to the messages.
+/// * `remove_label_ids` - The labels to remove from the messages.
+///
+/// # Returns
+/// A Result which is Ok if the messages were modified or an error on failure.
+pub async fn messages_batch_modify(
+    token: &str,
+    ids: &[String],
+    add_label_ids: &[&str],
+    remove_label_ids: &[&str],
+) -> Result<(), ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .post("https://gmail.googleapis.com/gmail/v1/users/me/messages/batchModify")
+        .header("Authorization", format!("Bearer {}", token))
+        .json(&json!({
+            "ids": ids,
+            "addLabelIds": add_label_ids,
+            "removeLabelIds": remove_label_ids,
+        }))
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            println!("batch modify error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+/// Retrieves the mailbox changes made after a given history ID
+/// by calling the users.history.list method of the Gmail API.
+///
+/// # Arguments
+/// * `token` - The access token for Gmail API authentication.
+/// * `start_history_id` - The history ID to list changes from.
+/// * `page_token` - Optional token for pagination.
+///
+/// # Returns
+/// A Result containing HistoryList on success, `HistoryError::Expired` if
+/// the start history ID is no longer available or `HistoryError::Failed`.
+pub async fn history_list(
+    token: &str,
+    start_history_id: &str,
+    page_token: Option<&str>,
+) -> Result<HistoryList, HistoryError> {
+    let client = reqwest::Client::new();
+    let mut request = client
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/history")
+        .header("Authorization", format!("Bearer {}", token))
+        .query(&[("startHistoryId", start_history_id)]);
+
+    if let Some(token) = page_token {
+        request = request.query(&[("pageToken", token)]);
+    }
+
+    match request.send().await {
+        Ok(response) if response.status() == reqwest::StatusCode::NOT_FOUND => {
+            Err(HistoryError::Expired)
+        }
+        Ok(response) => match response.json::<HistoryList>().await {
+            Ok(history_list) => Ok(history_list),
+            Err(e) => {
+                println!("json parsing error: {}", e);
+                Err(HistoryError::Failed)
+            }
+        },
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(HistoryError::Failed)
         }
+    }
+}
+
+/// Retrieves the profile of the user's Gmail account
+/// by calling the users.getProfile method of the Gmail API.
+pub async fn profile_get(token: &str) -> Result<Profile, ()> {
+    let client = reqwest::Client::new();
+    let result = client
+        .get("https://gmail.googleapis.com/gmail/v1/users/me/profile")
+        .header("Authorization", format!("Bearer {}", token))
+        .send()
+        .await;
+    match result {
+        Ok(response) => response.json::<Profile>().await.map_err(|_| ()),
         Err(_) => Err(()),
     }
 }
+
+pub async fn user_get(token: &str) -> Result<User, ()> {
+    let profile = profile_get(token).await?;
+    Ok(User {
+        email: profile.emailAddress,
+    })
+}
@@ -0,0 +1,198 @@
+use crate::cache::CachedMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Gmail deletes messages from the Trash for good after this long, so
+/// trashing cannot be undone any later.
+pub const TRASH_RETENTION_MILLIS: i64 = 30 * 86_400_000;
+
+/// The labels of one message before and after a modifying action.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MessageChange {
+    pub id: String,
+    pub labels_before: Vec<String>,
+    pub labels_after: Vec<String>,
+}
+
+impl MessageChange {
+    /// Records `message` getting `added` labels and losing `removed` ones.
+    pub fn new(message: &CachedMessage, added: &[&str], removed: &[&str]) -> Self {
+        Self::from_labels(&message.id, &message.labels, added, removed)
+    }
+
+    /// Records the message `id` which had `labels` getting `added` labels
+    /// and losing `removed` ones.
+    pub fn from_labels(id: &str, labels: &[String], added: &[&str], removed: &[&str]) -> Self {
+        let mut labels_after: Vec<String> = labels
+            .iter()
+            .filter(|label| !removed.contains(&label.as_str()))
+            .cloned()
+            .collect();
+        for label in added {
+            if !labels_after.iter().any(|l| l == label) {
+                labels_after.push(label.to_string());
+            }
+        }
+        Self {
+            id: id.to_string(),
+            labels_before: labels.to_vec(),
+            labels_after,
+        }
+    }
+
+    /// Returns whether the action moved the message to the Trash.
+    pub fn trashed(&self) -> bool {
+        self.labels_after.iter().any(|l| l == "TRASH")
+            && !self.labels_before.iter().any(|l| l == "TRASH")
+    }
+
+    /// Returns the labels to add and remove to put the message back the way
+    /// it was, leaving out TRASH which is undone by untrashing.
+    pub fn reverse(&self) -> (Vec<String>, Vec<String>) {
+        let add = self
+            .labels_before
+            .iter()
+            .filter(|l| *l != "TRASH" && !self.labels_after.contains(l))
+            .cloned()
+            .collect();
+        let remove = self
+            .labels_after
+            .iter()
+            .filter(|l| *l != "TRASH" && !self.labels_before.contains(l))
+            .cloned()
+            .collect();
+        (add, remove)
+    }
+}
+
+/// One modifying action taken on the mailbox.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JournalEntry {
+    /// Row ID in the cache database, 0 until the entry is stored.
+    #[serde(default)]
+    pub id: i64,
+    /// What was done, e.g. "trash" or "bulk_trash".
+    pub action: String,
+    /// Milliseconds since the epoch.
+    pub performed_at: i64,
+    pub changes: Vec<MessageChange>,
+    /// Threads trashed as a whole, whose messages were not all cached.
+    #[serde(default)]
+    pub thread_ids: Vec<String>,
+    /// Messages the action added, trashed again when it is undone.
+    #[serde(default)]
+    pub created_ids: Vec<String>,
+    #[serde(default)]
+    pub undone: bool,
+}
+
+impl JournalEntry {
+    pub fn new(action: &str) -> Self {
+        let performed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        Self {
+            id: 0,
+            action: action.to_string(),
+            performed_at,
+            changes: vec![],
+            thread_ids: vec![],
+            created_ids: vec![],
+            undone: false,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.thread_ids.is_empty()
+    }
+
+    /// Returns whether the Trash still holds what the action trashed.
+    pub fn within_trash_window(&self, now: i64) -> bool {
+        now - self.performed_at < TRASH_RETENTION_MILLIS
+    }
+
+    /// Groups the reverse label changes so that messages needing the same
+    /// change can be modified together.
+    pub fn reverse_label_changes(&self) -> BTreeMap<(Vec<String>, Vec<String>), Vec<String>> {
+        let mut groups: BTreeMap<(Vec<String>, Vec<String>), Vec<String>> = BTreeMap::new();
+        for change in &self.changes {
+            let (add, remove) = change.reverse();
+            if !add.is_empty() || !remove.is_empty() {
+                groups
+                    .entry((add, remove))
+                    .or_default()
+                    .push(change.id.clone());
+            }
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|label| label.to_string()).collect()
+    }
+
+    #[test]
+    fn reverses_an_archive() {
+        let change =
+            MessageChange::from_labels("1", &labels(&["INBOX", "UNREAD"]), &[], &["INBOX"]);
+        assert_eq!(change.labels_after, labels(&["UNREAD"]));
+        assert!(!change.trashed());
+        assert_eq!(change.reverse(), (labels(&["INBOX"]), labels(&[])));
+    }
+
+    #[test]
+    fn reverses_a_label_apply() {
+        let change = MessageChange::from_labels("1", &labels(&["INBOX"]), &["Label_3"], &[]);
+        assert_eq!(change.labels_after, labels(&["INBOX", "Label_3"]));
+        assert_eq!(change.reverse(), (labels(&[]), labels(&["Label_3"])));
+    }
+
+    #[test]
+    fn leaves_trash_to_untrashing() {
+        let change =
+            MessageChange::from_labels("1", &labels(&["INBOX", "Label_3"]), &["TRASH"], &["INBOX"]);
+        assert!(change.trashed());
+        assert_eq!(change.reverse(), (labels(&["INBOX"]), labels(&[])));
+
+        let already = MessageChange::from_labels("2", &labels(&["TRASH"]), &["TRASH"], &[]);
+        assert!(!already.trashed());
+        assert_eq!(already.reverse(), (labels(&[]), labels(&[])));
+    }
+
+    #[test]
+    fn keeps_labels_the_message_already_had() {
+        let change =
+            MessageChange::from_labels("1", &labels(&["INBOX", "Label_3"]), &["Label_3"], &[]);
+        assert_eq!(change.labels_after, labels(&["INBOX", "Label_3"]));
+        assert_eq!(change.reverse(), (labels(&[]), labels(&[])));
+    }
+
+    #[test]
+    fn groups_identical_reversals() {
+        let mut entry = JournalEntry::new("archive");
+        entry.changes = vec![
+            MessageChange::from_labels("1", &labels(&["INBOX"]), &[], &["INBOX"]),
+            MessageChange::from_labels("2", &labels(&["INBOX", "UNREAD"]), &[], &["INBOX"]),
+            MessageChange::from_labels("3", &labels(&["INBOX"]), &["Label_3"], &["INBOX"]),
+            // Not in the Inbox before, so archiving changed nothing
+            MessageChange::from_labels("4", &labels(&["SENT"]), &[], &["INBOX"]),
+        ];
+        let groups = entry.reverse_label_changes();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[&(labels(&["INBOX"]), labels(&[]))],
+            labels(&["1", "2"])
+        );
+        assert_eq!(
+            groups[&(labels(&["INBOX"]), labels(&["Label_3"]))],
+            labels(&["3"])
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use crate::attachments::safe_filename;
+use crate::cache::CachedMessage;
+use crate::gmail_client::{decode_base64url, message_get_raw};
+use crate::stats::civil_date;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Name of the file listing what was archived to a folder.
+pub const INDEX_FILE: &str = "index.json";
+
+/// Name of the mbox file messages are appended to.
+pub const MBOX_FILE: &str = "gmail-archive.mbox";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    /// All messages appended to one mbox file.
+    Mbox,
+    /// One `.eml` file per message.
+    Eml,
+}
+
+impl ArchiveFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "mbox" => Some(ArchiveFormat::Mbox),
+            "eml" => Some(ArchiveFormat::Eml),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Mbox => "mbox",
+            ArchiveFormat::Eml => "eml",
+        }
+    }
+}
+
+/// A message written to a local archive, as listed in its index.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArchivedMessage {
+    pub message_id: String,
+    pub thread_id: String,
+    pub subject: String,
+    pub sender: String,
+    /// Milliseconds since the epoch the message was received.
+    pub date: i64,
+    pub labels: Vec<String>,
+    /// Size of the raw message in bytes.
+    pub size: i64,
+    /// "mbox" or "eml".
+    pub format: String,
+    /// The mbox file or `.eml` file the message was written to.
+    pub path: String,
+    /// Milliseconds since the epoch the message was archived.
+    pub archived_at: i64,
+}
+
+#[derive(Serialize)]
+pub struct ArchiveReport {
+    pub folder: String,
+    pub index: String,
+    pub archived: Vec<ArchivedMessage>,
+    /// IDs of the messages which could not be archived.
+    pub failed: Vec<String>,
+}
+
+/// Formats the separator line starting each message of an mbox file,
+/// e.g. `From MAILER-DAEMON Thu Jan  1 00:00:00 1970`.
+fn mbox_from_line(millis: i64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (year, month, day) = civil_date(millis);
+    let days = millis.div_euclid(86_400_000);
+    let seconds = millis.rem_euclid(86_400_000) / 1000;
+    format!(
+        "From MAILER-DAEMON {} {} {:>2} {:02}:{:02}:{:02} {}\n",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        MONTHS[month as usize - 1],
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        year
+    )
+}
+
+/// Turns a raw message into an mbox entry: the separator line, the
+/// message with Unix line endings and lines starting with "From " quoted
+/// the mboxrd way, and a blank line.
+fn mbox_entry(raw: &[u8], millis: i64) -> Vec<u8> {
+    let mut entry = mbox_from_line(millis).into_bytes();
+    let text = raw.strip_suffix(b"\n").unwrap_or(raw);
+    for line in text.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line
+            .iter()
+            .skip_while(|b| **b == b'>')
+            .take(5)
+            .eq(b"From ".iter())
+        {
+            entry.push(b'>');
+        }
+        entry.extend_from_slice(line);
+        entry.push(b'\n');
+    }
+    entry.push(b'\n');
+    entry
+}
+
+/// Returns the name of the `.eml` file of a message, starting with the
+/// date it was received so that the files sort chronologically.
+fn eml_filename(message: &CachedMessage) -> String {
+    let (year, month, day) = civil_date(message.date);
+    let subject: String = message.subject.chars().take(60).collect();
+    safe_filename(&format!(
+        "{}-{:02}-{:02} {} {}.eml",
+        year, month, day, subject, message.id
+    ))
+}
+
+/// Reads the index of a folder, or an empty one if there is none yet.
+fn read_index(path: &Path) -> Vec<ArchivedMessage> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Fetches the raw `messages` and writes them to `folder` as an mbox file
+/// or `.eml` files, recording them in its index. Messages the index
+/// already lists in the same format are not written again.
+pub async fn archive_messages(
+    token: &str,
+    messages: &[CachedMessage],
+    folder: &Path,
+    format: ArchiveFormat,
+) -> ArchiveReport {
+    let index_path = folder.join(INDEX_FILE);
+    let mut index = read_index(&index_path);
+    let mut archived = vec![];
+    let mut failed = vec![];
+    for message in messages {
+        let previous = index.iter().find(|entry| {
+            entry.message_id == message.id
+                && entry.format == format.name()
+                && Path::new(&entry.path).exists()
+        });
+        if let Some(previous) = previous {
+            archived.push(previous.clone());
+            continue;
+        }
+        let raw = match message_get_raw(token, &message.id).await {
+            Ok(msg) => msg.raw.ok_or(()).and_then(|raw| decode_base64url(&raw)),
+            Err(_) => Err(()),
+        };
+        let Ok(raw) = raw else {
+            failed.push(message.id.clone());
+            continue;
+        };
+        let path = match format {
+            ArchiveFormat::Mbox => folder.join(MBOX_FILE),
+            ArchiveFormat::Eml => folder.join(eml_filename(message)),
+        };
+        let written = match format {
+            ArchiveFormat::Mbox => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(&mbox_entry(&raw, message.date))),
+            ArchiveFormat::Eml => std::fs::write(&path, &raw),
+        };
+        if let Err(e) = written {
+            println!("Could not write {}: {}", path.display(), e);
+            failed.push(message.id.clone());
+            continue;
+        }
+        let entry = ArchivedMessage {
+            message_id: message.id.clone(),
+            thread_id: message.thread_id.clone(),
+            subject: message.subject.clone(),
+            sender: message.sender.clone(),
+            date: message.date,
+            labels: message.labels.clone(),
+            size: raw.len() as i64,
+            format: format.name().to_string(),
+            path: path.display().to_string(),
+            archived_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0),
+        };
+        index.push(entry.clone());
+        archived.push(entry);
+    }
+    match serde_json::to_string_pretty(&index) {
+        Ok(data) => {
+            if let Err(e) = std::fs::write(&index_path, data) {
+                println!("Could not write the index: {}", e);
+            }
+        }
+        Err(e) => println!("Could not serialize the index: {}", e),
+    }
+    ArchiveReport {
+        folder: folder.display().to_string(),
+        index: index_path.display().to_string(),
+        archived,
+        failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPOCH: &str = "From MAILER-DAEMON Thu Jan  1 00:00:00 1970\n";
+
+    #[test]
+    fn formats_the_separator_line() {
+        assert_eq!(mbox_from_line(0), EPOCH);
+        // 2024-02-29 13:05:09 UTC
+        assert_eq!(
+            mbox_from_line(1_709_211_909_000),
+            "From MAILER-DAEMON Thu Feb 29 13:05:09 2024\n"
+        );
+        assert_eq!(
+            mbox_from_line(-1000),
+            "From MAILER-DAEMON Wed Dec 31 23:59:59 1969\n"
+        );
+    }
+
+    #[test]
+    fn quotes_from_lines() {
+        let raw = b"Subject: Hi\n\nFrom here on\n>From before\nFrom\nfrom me\n";
+        let entry = String::from_utf8(mbox_entry(raw, 0)).unwrap();
+        assert_eq!(
+            entry,
+            format!(
+                "{}Subject: Hi\n\n>From here on\n>>From before\nFrom\nfrom me\n\n",
+                EPOCH
+            )
+        );
+    }
+
+    #[test]
+    fn converts_crlf_line_endings() {
+        let raw = b"Subject: Hi\r\n\r\nFrom a\r\nbody\r\n";
+        let entry = String::from_utf8(mbox_entry(raw, 0)).unwrap();
+        assert_eq!(entry, format!("{}Subject: Hi\n\n>From a\nbody\n\n", EPOCH));
+    }
+
+    #[test]
+    fn ends_every_entry_with_one_blank_line() {
+        let expected = format!("{}Subject: Hi\n\nbody\n\n", EPOCH);
+        for raw in [
+            &b"Subject: Hi\n\nbody"[..],
+            b"Subject: Hi\n\nbody\n",
+            b"Subject: Hi\r\n\r\nbody",
+        ] {
+            assert_eq!(String::from_utf8(mbox_entry(raw, 0)).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn names_eml_files_by_date_subject_and_id() {
+        let mut message = CachedMessage {
+            id: "18c0ffee".to_string(),
+            thread_id: "18c0ffee".to_string(),
+            subject: "Re: Q3/Q4 plans?".to_string(),
+            sender: "a@example.com".to_string(),
+            date: 1_709_211_909_000,
+            labels: Vec::new(),
+            size_estimate: 0,
+            attachments: Vec::new(),
+            list_id: String::new(),
+            list_unsubscribe: String::new(),
+            list_unsubscribe_post: String::new(),
+            precedence: String::new(),
+        };
+        assert_eq!(
+            eml_filename(&message),
+            "2024-02-29 Re_ Q3_Q4 plans_ 18c0ffee.eml"
+        );
+
+        message.subject = "x".repeat(100);
+        assert_eq!(
+            eml_filename(&message),
+            format!("2024-02-29 {} 18c0ffee.eml", "x".repeat(60))
+        );
+    }
+}
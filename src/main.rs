@@ -11,12 +11,38 @@ use iced::{run, Application, Element, Settings, Theme};
 mod app_gpui_ui;
 
 mod app;
+mod attachments;
+mod cache;
+mod classifier;
+mod duplicates;
+mod export;
 mod gmail_client;
+mod journal;
+mod mail_archive;
+mod mime_strip;
+mod newsletters;
+mod report;
+mod rules;
+mod scan;
 mod server;
+mod settings;
+mod snapshot;
+mod stats;
+mod sync;
+mod unsubscribe;
 
 #[cfg(feature = "iced_ui")]
 mod app_iced_ui;
 
+#[cfg(feature = "cli")]
+mod cli;
+
+// The GUI front ends take precedence when one of them is enabled as well
+#[cfg(all(feature = "cli", not(feature = "iced_ui"), not(feature = "gpui_ui")))]
+fn main() {
+    std::process::exit(cli::run(std::env::args().skip(1).collect()));
+}
+
 #[cfg(feature = "iced_ui")]
 fn main() -> iced::Result {
     iced::application("Gmail Cleaner", app_iced_ui::update, app_iced_ui::view)
@@ -70,7 +96,7 @@ fn quit(_: &Quit, cx: &mut App) {
 #[cfg(feature = "gpui_ui")]
 fn about(_: &About, cx: &mut App) {
     // Show about dialog in a separate system window
-    let about_text = "Gmail Cleaner is a desktop app which helps you find out which emails are taking up storage space in your Gmail account. This app runs on your desktop and does not send your email to any server. Therefore it is the most secure way of cleaning up your Gmail account. You don't need to give any permissions to this app, neither do you need to provide credentials. When you start it, a browser window opens up with Gmail's login page. Once Gmail authorizes you the app visually shows you what you can delete. The app does not read your email or cannot modify your Gmail account in any way.";
+    let about_text = "Gmail Cleaner is a desktop app which helps you find out which emails are taking up storage space in your Gmail account. This app runs on your desktop and does not send your email to any server. Therefore it is the most secure way of cleaning up your Gmail account. You don't need to give any permissions to this app, neither do you need to provide credentials. When you start it, a browser window opens up with Gmail's login page. Once Gmail authorizes you the app visually shows you what you can delete. By default the app only gets read-only access and cannot modify your Gmail account in any way.";
 
     let _ = MessageDialog::new()
         .set_type(MessageType::Info)
@@ -0,0 +1,614 @@
+use crate::stats::format_size;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// An attachment taken out of a message.
+#[derive(Serialize, Clone)]
+pub struct RemovedPart {
+    /// Gmail's ID of the part, e.g. "1" or "1.2".
+    pub part_id: String,
+    pub filename: String,
+    pub mime_type: String,
+    /// Bytes the encoded part took up in the message.
+    pub size: i64,
+}
+
+/// Splits a MIME entity into its header block and body. The header block
+/// keeps its last line break; the blank line between the two is dropped.
+fn split_entity(data: &[u8]) -> (&[u8], &[u8]) {
+    if data.starts_with(b"\r\n") {
+        return (&[], &data[2..]);
+    }
+    if data.starts_with(b"\n") {
+        return (&[], &data[1..]);
+    }
+    let mut start = 0;
+    while let Some(offset) = data[start..].iter().position(|b| *b == b'\n') {
+        let line_end = start + offset + 1;
+        let rest = &data[line_end..];
+        if rest.starts_with(b"\r\n") {
+            return (&data[..line_end], &rest[2..]);
+        }
+        if rest.starts_with(b"\n") {
+            return (&data[..line_end], &rest[1..]);
+        }
+        start = line_end;
+    }
+    (data, &[])
+}
+
+/// Splits a header block into headers, each with its continuation lines.
+fn header_fields(headers: &[u8]) -> Vec<&[u8]> {
+    let mut fields: Vec<&[u8]> = vec![];
+    let mut field_start = 0;
+    let mut start = 0;
+    while start < headers.len() {
+        let line_end = headers[start..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|offset| start + offset + 1)
+            .unwrap_or(headers.len());
+        let continuation = headers[start] == b' ' || headers[start] == b'\t';
+        if !continuation && start > field_start {
+            fields.push(&headers[field_start..start]);
+            field_start = start;
+        }
+        start = line_end;
+    }
+    if start > field_start {
+        fields.push(&headers[field_start..start]);
+    }
+    fields
+}
+
+/// Returns the unfolded value of the header called `name`.
+fn header_value(headers: &[u8], name: &str) -> Option<String> {
+    header_fields(headers).into_iter().find_map(|field| {
+        let field = String::from_utf8_lossy(field);
+        let (field_name, value) = field.split_once(':')?;
+        if !field_name.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value: Vec<&str> = value.split(['\r', '\n']).map(str::trim).collect();
+        Some(value.join(" ").trim().to_string())
+    })
+}
+
+/// Returns the parameter called `name` of a header value such as
+/// `attachment; filename="report.pdf"`. RFC 2231 encoded values like
+/// `filename*=utf-8''r%C3%A9sum%C3%A9.pdf` are decoded too.
+fn header_param(value: &str, name: &str) -> Option<String> {
+    // Split at semicolons outside quotes
+    let mut params = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                escaped = false;
+                current.push(c);
+            }
+            '\\' if quoted => {
+                escaped = true;
+                current.push(c);
+            }
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ';' if !quoted => params.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    params.push(current);
+
+    let mut extended = None;
+    for param in params.iter().skip(1) {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = unquote(value.trim());
+        if key == name {
+            return Some(value);
+        }
+        if key == format!("{}*", name) {
+            let encoded = value.splitn(3, '\'').nth(2).unwrap_or(&value);
+            extended = Some(
+                urlencoding::decode(encoded)
+                    .map(|decoded| decoded.into_owned())
+                    .unwrap_or_else(|_| encoded.to_string()),
+            );
+        }
+    }
+    extended
+}
+
+/// Removes the quotes and backslash escapes of a quoted string such as
+/// `"draft \"2\".txt"`; other values are returned as they are.
+fn unquote(value: &str) -> String {
+    let Some(inner) = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+    else {
+        return value.to_string();
+    };
+    let mut unquoted = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted
+}
+
+/// Finds the boundary delimiter lines of a multipart body as (start, end,
+/// closing) byte ranges, `end` including the line break.
+fn delimiters(body: &[u8], boundary: &str) -> Vec<(usize, usize, bool)> {
+    let marker = format!("--{}", boundary).into_bytes();
+    let mut found = vec![];
+    let mut start = 0;
+    while start < body.len() {
+        let end = body[start..]
+            .iter()
+            .position(|b| *b == b'\n')
+            .map(|offset| start + offset + 1)
+            .unwrap_or(body.len());
+        let line = &body[start..end];
+        if let Some(rest) = line.strip_prefix(marker.as_slice()) {
+            let closing = rest.starts_with(b"--");
+            let rest = if closing { &rest[2..] } else { rest };
+            if rest.iter().all(|b| b.is_ascii_whitespace()) {
+                found.push((start, end, closing));
+                if closing {
+                    break;
+                }
+            }
+        }
+        start = end;
+    }
+    found
+}
+
+/// Returns the length of the line break ending `data`, which belongs to
+/// the delimiter following a body part.
+fn trailing_break(data: &[u8]) -> usize {
+    if data.ends_with(b"\r\n") {
+        2
+    } else if data.ends_with(b"\n") {
+        1
+    } else {
+        0
+    }
+}
+
+/// Rewrites MIME messages with some of their attachments replaced by a
+/// short text note.
+pub struct AttachmentStripper {
+    /// Gmail part IDs of the attachments to remove.
+    part_ids: HashSet<String>,
+    /// Where copies of parts were saved, by Gmail part ID, to mention in
+    /// their notes.
+    saved_paths: HashMap<String, String>,
+    /// Line break used by the message.
+    line_break: &'static str,
+    removed: Vec<RemovedPart>,
+}
+
+impl AttachmentStripper {
+    pub fn new(part_ids: HashSet<String>, saved_paths: HashMap<String, String>) -> Self {
+        Self {
+            part_ids,
+            saved_paths,
+            line_break: "\r\n",
+            removed: vec![],
+        }
+    }
+
+    /// Returns the message with its attachments replaced, along with what
+    /// was removed. The message is returned unchanged if nothing was.
+    pub fn strip(mut self, raw: &[u8]) -> (Vec<u8>, Vec<RemovedPart>) {
+        let crlf = raw.windows(2).any(|pair| pair == b"\r\n");
+        self.line_break = if crlf { "\r\n" } else { "\n" };
+        let stripped = self.entity(raw, "");
+        (stripped, self.removed)
+    }
+
+    /// Rewrites one MIME entity. `part_id` follows Gmail's numbering: the
+    /// message is "", its parts "0", "1", ... and their parts "1.0" etc.
+    fn entity(&mut self, data: &[u8], part_id: &str) -> Vec<u8> {
+        let (headers, body) = split_entity(data);
+        let content_type = header_value(headers, "Content-Type").unwrap_or_default();
+        let mime_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase();
+
+        if mime_type.starts_with("multipart/") {
+            if let Some(boundary) = header_param(&content_type, "boundary") {
+                return self.multipart(data, body, &boundary, part_id);
+            }
+            return data.to_vec();
+        }
+
+        let disposition = header_value(headers, "Content-Disposition").unwrap_or_default();
+        let filename = header_param(&disposition, "filename")
+            .or_else(|| header_param(&content_type, "name"))
+            .unwrap_or_default();
+        let is_attachment =
+            !filename.is_empty() || disposition.trim().to_lowercase().starts_with("attachment");
+        if !is_attachment || !self.part_ids.contains(part_id) {
+            return data.to_vec();
+        }
+
+        let filename = if filename.is_empty() {
+            "attachment".to_string()
+        } else {
+            filename
+        };
+        let size = body.len() as i64;
+        let mut note = format!(
+            "The attachment {} ({}, {}) was removed to save space.",
+            filename,
+            if mime_type.is_empty() {
+                "text/plain"
+            } else {
+                &mime_type
+            },
+            format_size(size)
+        );
+        if let Some(path) = self.saved_paths.get(part_id) {
+            note.push_str(&format!(" A copy was saved to {}.", path));
+        }
+        self.removed.push(RemovedPart {
+            part_id: part_id.to_string(),
+            filename,
+            mime_type,
+            size,
+        });
+
+        // Keep every header but the ones describing the old content
+        let mut rewritten = vec![];
+        for field in header_fields(headers) {
+            let name = field.split(|b| *b == b':').next().unwrap_or_default();
+            if !String::from_utf8_lossy(name)
+                .trim()
+                .to_lowercase()
+                .starts_with("content-")
+            {
+                rewritten.extend_from_slice(field);
+            }
+        }
+        let line_break = self.line_break;
+        rewritten.extend_from_slice(
+            format!(
+                "Content-Type: text/plain; charset=\"utf-8\"{0}\
+                 Content-Transfer-Encoding: 8bit{0}\
+                 Content-Disposition: inline{0}{0}{1}{0}",
+                line_break, note
+            )
+            .as_bytes(),
+        );
+        rewritten
+    }
+
+    /// Rewrites the parts of a multipart entity, keeping the headers,
+    /// preamble, delimiters and epilogue byte for byte.
+    fn multipart(&mut self, data: &[u8], body: &[u8], boundary: &str, part_id: &str) -> Vec<u8> {
+        let body_start = data.len() - body.len();
+        let delimiters = delimiters(body, boundary);
+        let Some(&(_, first_end, _)) = delimiters.first() else {
+            return data.to_vec();
+        };
+        let mut rewritten = data[..body_start + first_end].to_vec();
+        for (index, pair) in delimiters.windows(2).enumerate() {
+            let (_, part_start, _) = pair[0];
+            let (next_start, next_end, _) = pair[1];
+            let part = &body[part_start..next_start];
+            let part_end = part_start + part.len() - trailing_break(part);
+            let child_id = if part_id.is_empty() {
+                index.to_string()
+            } else {
+                format!("{}.{}", part_id, index)
+            };
+            rewritten.extend(self.entity(&body[part_start..part_end], &child_id));
+            rewritten.extend_from_slice(&body[part_end..next_end]);
+        }
+        let &(_, last_end, _) = delimiters.last().unwrap_or(&(0, first_end, false));
+        rewritten.extend_from_slice(&body[last_end..]);
+        rewritten
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// multipart/mixed holding a multipart/alternative, which holds a
+    /// multipart/mixed, with a preamble and an epilogue. Gmail numbers
+    /// its parts "0", "0.0", "0.1", "0.1.0", "0.1.1" and "1".
+    const NESTED: &str = concat!(
+        "From: sender@example.com\r\n",
+        "To: me@example.com\r\n",
+        "Subject: Nested\r\n",
+        "MIME-Version: 1.0\r\n",
+        "Content-Type: multipart/mixed; boundary=\"outer\"\r\n",
+        "\r\n",
+        "This is a multi-part message in MIME format.\r\n",
+        "--outer\r\n",
+        "Content-Type: multipart/alternative; boundary=\"alt\"\r\n",
+        "\r\n",
+        "--alt\r\n",
+        "Content-Type: text/plain; charset=\"utf-8\"\r\n",
+        "\r\n",
+        "Hello\r\n",
+        "--alt\r\n",
+        "Content-Type: multipart/mixed; boundary=inner\r\n",
+        "\r\n",
+        "--inner\r\n",
+        "Content-Type: text/html; charset=\"utf-8\"\r\n",
+        "\r\n",
+        "<p>Hello</p>\r\n",
+        "--inner\r\n",
+        "Content-Type: image/png; name=\"photo.png\"\r\n",
+        "Content-Disposition: attachment; filename=\"photo.png\"\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "Content-ID: <photo>\r\n",
+        "\r\n",
+        "iVBORw0KGgo=\r\n",
+        "--inner--\r\n",
+        "--alt--\r\n",
+        "--outer\r\n",
+        "Content-Type: application/pdf; name=\"report.pdf\"\r\n",
+        "Content-Disposition: attachment;\r\n",
+        "\tfilename=\"report.pdf\"\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "X-Attachment-Id: f_1\r\n",
+        "\r\n",
+        "JVBERi0xLjQK\r\n",
+        "--outer--\r\n",
+        "Epilogue text\r\n",
+    );
+
+    /// Part "0.1.1" of `NESTED`, without the line break before the next
+    /// delimiter.
+    const PHOTO_PART: &str = concat!(
+        "Content-Type: image/png; name=\"photo.png\"\r\n",
+        "Content-Disposition: attachment; filename=\"photo.png\"\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "Content-ID: <photo>\r\n",
+        "\r\n",
+        "iVBORw0KGgo=",
+    );
+
+    /// Part "1" of `NESTED`.
+    const REPORT_PART: &str = concat!(
+        "Content-Type: application/pdf; name=\"report.pdf\"\r\n",
+        "Content-Disposition: attachment;\r\n",
+        "\tfilename=\"report.pdf\"\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "X-Attachment-Id: f_1\r\n",
+        "\r\n",
+        "JVBERi0xLjQK",
+    );
+
+    fn strip(raw: &str, part_ids: &[&str]) -> (String, Vec<RemovedPart>) {
+        strip_saved(raw, part_ids, HashMap::new())
+    }
+
+    fn strip_saved(
+        raw: &str,
+        part_ids: &[&str],
+        saved_paths: HashMap<String, String>,
+    ) -> (String, Vec<RemovedPart>) {
+        let part_ids = part_ids.iter().map(|id| id.to_string()).collect();
+        let (stripped, removed) =
+            AttachmentStripper::new(part_ids, saved_paths).strip(raw.as_bytes());
+        (String::from_utf8(stripped).unwrap(), removed)
+    }
+
+    /// The text part an attachment is replaced with.
+    fn placeholder(kept_headers: &str, note: &str, line_break: &str) -> String {
+        format!(
+            "{1}Content-Type: text/plain; charset=\"utf-8\"{0}\
+             Content-Transfer-Encoding: 8bit{0}\
+             Content-Disposition: inline{0}{0}{2}{0}",
+            line_break, kept_headers, note
+        )
+    }
+
+    fn removed_ids(removed: &[RemovedPart]) -> Vec<&str> {
+        removed.iter().map(|part| part.part_id.as_str()).collect()
+    }
+
+    #[test]
+    fn replaces_only_the_selected_parts_of_nested_multiparts() {
+        let (stripped, removed) = strip(NESTED, &["0.1.1", "1"]);
+        let expected = NESTED
+            .replace(
+                PHOTO_PART,
+                &placeholder(
+                    "",
+                    "The attachment photo.png (image/png, 0.0 KB) was removed to save space.",
+                    "\r\n",
+                ),
+            )
+            .replace(
+                REPORT_PART,
+                &placeholder(
+                    "X-Attachment-Id: f_1\r\n",
+                    "The attachment report.pdf (application/pdf, 0.0 KB) was removed to save space.",
+                    "\r\n",
+                ),
+            );
+        assert_eq!(stripped, expected);
+        assert_eq!(removed_ids(&removed), ["0.1.1", "1"]);
+        assert_eq!(removed[0].filename, "photo.png");
+        assert_eq!(removed[0].mime_type, "image/png");
+        assert_eq!(removed[0].size, 12);
+        assert_eq!(removed[1].filename, "report.pdf");
+        assert_eq!(removed[1].mime_type, "application/pdf");
+    }
+
+    #[test]
+    fn follows_gmail_part_numbering() {
+        let (stripped, removed) = strip(NESTED, &["0.1.1"]);
+        assert_eq!(removed_ids(&removed), ["0.1.1"]);
+        assert!(!stripped.contains("iVBORw0KGgo="));
+        assert!(stripped.contains(REPORT_PART));
+
+        // IDs which belong to the sibling numbering of another level
+        let (stripped, removed) = strip(NESTED, &["2", "1.1", "0.1.2", "0.2"]);
+        assert!(removed.is_empty());
+        assert_eq!(stripped, NESTED);
+    }
+
+    #[test]
+    fn keeps_parts_which_are_not_attachments() {
+        // Text bodies and multipart containers are never replaced, even
+        // when their IDs are selected
+        let (stripped, removed) = strip(NESTED, &["", "0", "0.0", "0.1", "0.1.0"]);
+        assert!(removed.is_empty());
+        assert_eq!(stripped, NESTED);
+    }
+
+    #[test]
+    fn leaves_the_message_unchanged_without_a_selection() {
+        let (stripped, removed) = strip(NESTED, &[]);
+        assert!(removed.is_empty());
+        assert_eq!(stripped, NESTED);
+    }
+
+    #[test]
+    fn keeps_bare_line_feeds() {
+        let raw = NESTED.replace("\r\n", "\n");
+        let (stripped, removed) = strip(&raw, &["1"]);
+        let expected = raw.replace(
+            &REPORT_PART.replace("\r\n", "\n"),
+            &placeholder(
+                "X-Attachment-Id: f_1\n",
+                "The attachment report.pdf (application/pdf, 0.0 KB) was removed to save space.",
+                "\n",
+            ),
+        );
+        assert_eq!(stripped, expected);
+        assert!(!stripped.contains('\r'));
+        assert_eq!(removed_ids(&removed), ["1"]);
+    }
+
+    #[test]
+    fn keeps_the_preamble_and_epilogue() {
+        let (stripped, _) = strip(NESTED, &["0.1.1", "1"]);
+        let preamble_end = NESTED.find("--outer\r\n").unwrap();
+        assert_eq!(stripped[..preamble_end], NESTED[..preamble_end]);
+        assert!(stripped.ends_with("\r\n--outer--\r\nEpilogue text\r\n"));
+    }
+
+    #[test]
+    fn decodes_quoted_and_rfc_2231_filenames() {
+        let raw = concat!(
+            "Content-Type: multipart/mixed; boundary=\"b\"\r\n",
+            "\r\n",
+            "--b\r\n",
+            "Content-Type: application/pdf\r\n",
+            "Content-Disposition: attachment; filename*=utf-8''r%C3%A9sum%C3%A9.pdf\r\n",
+            "\r\n",
+            "AAAA\r\n",
+            "--b\r\n",
+            "Content-Type: text/plain\r\n",
+            "Content-Disposition: attachment; filename=\"notes; draft \\\"2\\\".txt\"; size=4\r\n",
+            "\r\n",
+            "BBBB\r\n",
+            "--b\r\n",
+            "Content-Type: image/jpeg; name=\"scan.jpg\"\r\n",
+            "\r\n",
+            "CCCC\r\n",
+            "--b--\r\n",
+        );
+        let (stripped, removed) = strip(raw, &["0", "1", "2"]);
+        assert_eq!(removed_ids(&removed), ["0", "1", "2"]);
+        assert_eq!(removed[0].filename, "résumé.pdf");
+        assert_eq!(removed[1].filename, "notes; draft \"2\".txt");
+        assert_eq!(removed[2].filename, "scan.jpg");
+        assert!(stripped.contains("The attachment résumé.pdf (application/pdf,"));
+        for body in ["AAAA", "BBBB", "CCCC"] {
+            assert!(!stripped.contains(body));
+        }
+    }
+
+    #[test]
+    fn leaves_a_part_without_closing_delimiter_untouched() {
+        // A truncated message: the last part is not followed by a delimiter
+        // and may be incomplete, so it is kept as it is
+        let raw = concat!(
+            "Content-Type: multipart/mixed; boundary=\"b\"\r\n",
+            "\r\n",
+            "--b\r\n",
+            "Content-Type: application/zip; name=\"a.zip\"\r\n",
+            "\r\n",
+            "UEsDBA==\r\n",
+            "--b\r\n",
+            "Content-Type: application/zip; name=\"b.zip\"\r\n",
+            "\r\n",
+            "UEsDBB==\r\n",
+        );
+        let (stripped, removed) = strip(raw, &["0", "1"]);
+        assert_eq!(removed_ids(&removed), ["0"]);
+        assert!(!stripped.contains("UEsDBA=="));
+        let last = raw.rfind("--b\r\n").unwrap();
+        assert!(stripped.ends_with(&raw[last..]));
+        assert!(stripped.starts_with(&raw[..raw.find("Content-Type: application/zip").unwrap()]));
+    }
+
+    #[test]
+    fn leaves_a_multipart_without_delimiters_untouched() {
+        let raw = concat!(
+            "Content-Type: multipart/mixed; boundary=\"missing\"\r\n",
+            "\r\n",
+            "--other\r\n",
+            "Content-Type: application/pdf; name=\"a.pdf\"\r\n",
+            "\r\n",
+            "AAAA\r\n",
+        );
+        let (stripped, removed) = strip(raw, &["0"]);
+        assert!(removed.is_empty());
+        assert_eq!(stripped, raw);
+    }
+
+    #[test]
+    fn strips_a_message_which_is_an_attachment() {
+        let raw = concat!(
+            "From: scanner@example.com\r\n",
+            "Subject: Scan\r\n",
+            "Content-Type: application/pdf; name=\"scan.pdf\"\r\n",
+            "Content-Disposition: attachment; filename=\"scan.pdf\"\r\n",
+            "\r\n",
+            "JVBERi0xLjQK\r\n",
+        );
+        let (stripped, removed) = strip(raw, &[""]);
+        assert_eq!(removed_ids(&removed), [""]);
+        assert_eq!(
+            stripped,
+            placeholder(
+                "From: scanner@example.com\r\nSubject: Scan\r\n",
+                "The attachment scan.pdf (application/pdf, 0.0 KB) was removed to save space.",
+                "\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn mentions_where_a_copy_was_saved() {
+        let saved_paths = HashMap::from([("1".to_string(), "/tmp/report.pdf".to_string())]);
+        let (stripped, _) = strip_saved(NESTED, &["1"], saved_paths);
+        assert!(stripped.contains(
+            "The attachment report.pdf (application/pdf, 0.0 KB) was removed to save space. \
+             A copy was saved to /tmp/report.pdf.\r\n"
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::cache::CachedMessage;
+use crate::stats::sender_address;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Returns whether a message looks like mailing list or marketing mail:
+/// it has list headers, is marked as bulk mail, or Gmail filed it under
+/// Promotions.
+pub fn is_bulk(message: &CachedMessage) -> bool {
+    let precedence = message.precedence.trim().to_lowercase();
+    !message.list_id.is_empty()
+        || !message.list_unsubscribe.is_empty()
+        || precedence == "bulk"
+        || precedence == "list"
+        || message
+            .labels
+            .iter()
+            .any(|label| label == "CATEGORY_PROMOTIONS")
+}
+
+/// Splits a `List-Id` header value such as `"Weekly News" <news.example.com>`
+/// into its description and identifier.
+fn parse_list_id(value: &str) -> (String, String) {
+    match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => (
+            value[..start].trim().trim_matches('"').to_string(),
+            value[start + 1..end].trim().to_lowercase(),
+        ),
+        _ => (String::new(), value.trim().to_lowercase()),
+    }
+}
+
+/// Storage used by the messages of one mailing list. Mail without a
+/// `List-Id` is grouped by sender address.
+#[derive(Serialize)]
+pub struct ListTotal {
+    pub list_id: String,
+    pub name: String,
+    pub sender: String,
+    pub message_count: usize,
+    pub total_size: i64,
+    /// The `List-Unsubscribe` header of the most recent message.
+    pub unsubscribe: String,
+    /// The `List-Unsubscribe-Post` header sent along with `unsubscribe`.
+    pub unsubscribe_post: String,
+    /// Milliseconds since the epoch of the most recent message.
+    pub latest_date: i64,
+}
+
+/// Groups the bulk messages by list and returns the lists using the most
+/// storage first.
+pub fn lists_by_size(messages: &[CachedMessage]) -> Vec<ListTotal> {
+    let mut lists: HashMap<String, ListTotal> = HashMap::new();
+    for message in messages.iter().filter(|message| is_bulk(message)) {
+        let sender = sender_address(&message.sender);
+        let (name, list_id) = if message.list_id.is_empty() {
+            (String::new(), sender.clone())
+        } else {
+            parse_list_id(&message.list_id)
+        };
+        let total = lists.entry(list_id.clone()).or_insert(ListTotal {
+            list_id,
+            name: String::new(),
+            sender: String::new(),
+            message_count: 0,
+            total_size: 0,
+            unsubscribe: String::new(),
+            unsubscribe_post: String::new(),
+            latest_date: i64::MIN,
+        });
+        total.message_count += 1;
+        total.total_size += message.size_estimate as i64;
+        let newer = message.date > total.latest_date;
+        if newer {
+            total.latest_date = message.date;
+            total.sender = sender;
+        }
+        if !name.is_empty() && (newer || total.name.is_empty()) {
+            total.name = name;
+        }
+        if !message.list_unsubscribe.is_empty() && (newer || total.unsubscribe.is_empty()) {
+            total.unsubscribe = message.list_unsubscribe.clone();
+            total.unsubscribe_post = message.list_unsubscribe_post.clone();
+        }
+    }
+    let mut totals: Vec<ListTotal> = lists.into_values().collect();
+    totals.sort_by_key(|total| Reverse(total.total_size));
+    totals
+}
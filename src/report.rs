@@ -0,0 +1,242 @@
+use crate::cache::CachedMessage;
+use crate::classifier::SizeClassifier;
+use crate::stats::{format_size, iso_date, labels_by_size, senders_by_size, size_by_age};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Number of senders listed in the report.
+const TOP_SENDERS: usize = 25;
+
+/// Number of largest messages listed in the report.
+const TOP_MESSAGES: usize = 50;
+
+/// One line of a breakdown table, with the numbers formatted for reading.
+#[derive(Serialize)]
+pub struct ReportRow {
+    pub name: String,
+    pub message_count: usize,
+    pub size: String,
+    /// Share of the total size, e.g. "12.5%".
+    pub share: String,
+}
+
+impl ReportRow {
+    fn new(name: &str, message_count: usize, size: i64, total_size: i64) -> Self {
+        Self {
+            name: name.to_string(),
+            message_count,
+            size: format_size(size),
+            share: format!(
+                "{:.1}%",
+                if total_size > 0 {
+                    size as f64 * 100.0 / total_size as f64
+                } else {
+                    0.0
+                }
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ReportMessage {
+    pub subject: String,
+    pub sender: String,
+    /// `YYYY-MM-DD` the message was received.
+    pub date: String,
+    pub size: String,
+    pub bucket: String,
+    pub link: String,
+}
+
+/// Storage summary of the scanned messages, rendered by the report
+/// template as a standalone page.
+#[derive(Serialize)]
+pub struct StorageReport {
+    pub account: String,
+    pub generated_at: String,
+    pub message_count: usize,
+    pub total_size: String,
+    pub buckets: Vec<ReportRow>,
+    pub senders: Vec<ReportRow>,
+    pub labels: Vec<ReportRow>,
+    pub ages: Vec<ReportRow>,
+    pub largest: Vec<ReportMessage>,
+}
+
+impl StorageReport {
+    pub fn new(
+        account: &str,
+        messages: &[CachedMessage],
+        classifier: &SizeClassifier,
+        label_names: &HashMap<String, String>,
+        now: i64,
+    ) -> Self {
+        let total_size: i64 = messages.iter().map(|m| m.size_estimate as i64).sum();
+
+        let buckets = classifier
+            .buckets
+            .iter()
+            .map(|bucket| {
+                let (count, size) = messages
+                    .iter()
+                    .filter(|m| classifier.classify(m.size_estimate as i64) == bucket.name)
+                    .fold((0, 0), |(count, size), m| {
+                        (count + 1, size + m.size_estimate as i64)
+                    });
+                ReportRow::new(&bucket.name, count, size, total_size)
+            })
+            .collect();
+
+        let senders = senders_by_size(messages, false)
+            .into_iter()
+            .take(TOP_SENDERS)
+            .map(|s| ReportRow::new(&s.sender, s.message_count, s.total_size, total_size))
+            .collect();
+
+        let labels = labels_by_size(messages, label_names)
+            .into_iter()
+            .map(|l| ReportRow::new(&l.name, l.message_count, l.total_size, total_size))
+            .collect();
+
+        let ages = size_by_age(messages, true)
+            .into_iter()
+            .map(|a| ReportRow::new(&a.period, a.message_count, a.total_size, total_size))
+            .collect();
+
+        let mut largest: Vec<&CachedMessage> = messages.iter().collect();
+        largest.sort_by_key(|message| Reverse(message.size_estimate));
+        let largest = largest
+            .into_iter()
+            .take(TOP_MESSAGES)
+            .map(|m| ReportMessage {
+                subject: m.subject.clone(),
+                sender: m.sender.clone(),
+                date: iso_date(m.date)[..10].to_string(),
+                size: format_size(m.size_estimate as i64),
+                bucket: classifier.classify(m.size_estimate as i64).to_string(),
+                link: format!("https://mail.google.com/mail/u/0/#all/{}", m.thread_id),
+            })
+            .collect();
+
+        Self {
+            account: account.to_string(),
+            generated_at: iso_date(now).replace('T', " ").replace('Z', " UTC"),
+            message_count: messages.len(),
+            total_size: format_size(total_size),
+            buckets,
+            senders,
+            labels,
+            ages,
+            largest,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classifier::SizeBucket;
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn classifier() -> SizeClassifier {
+        SizeClassifier {
+            buckets: vec![
+                SizeBucket {
+                    name: "small".to_string(),
+                    min_size: 0,
+                },
+                SizeBucket {
+                    name: "large".to_string(),
+                    min_size: 3_001,
+                },
+            ],
+        }
+    }
+
+    /// 60 messages from different senders, of 100 to 6,000 bytes.
+    fn messages() -> Vec<CachedMessage> {
+        (0..60)
+            .map(|i| CachedMessage {
+                id: format!("m{}", i),
+                thread_id: format!("t{}", i),
+                subject: format!("Message {}", i),
+                sender: format!("s{}@example.com", i),
+                date: NOW - i * 86_400_000,
+                labels: vec![],
+                size_estimate: (i as i32 + 1) * 100,
+                attachments: vec![],
+                list_id: String::new(),
+                list_unsubscribe: String::new(),
+                list_unsubscribe_post: String::new(),
+                precedence: String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_buckets_and_shares() {
+        let report = StorageReport::new(
+            "a@example.com",
+            &messages(),
+            &classifier(),
+            &HashMap::new(),
+            NOW,
+        );
+        assert_eq!(report.message_count, 60);
+        assert_eq!(report.total_size, "178.7 KB");
+
+        let small = &report.buckets[0];
+        assert_eq!(small.name, "small");
+        assert_eq!(small.message_count, 30);
+        assert_eq!(small.size, "45.4 KB");
+        assert_eq!(small.share, "25.4%");
+        let large = &report.buckets[1];
+        assert_eq!(large.name, "large");
+        assert_eq!(large.message_count, 30);
+        assert_eq!(large.size, "133.3 KB");
+        assert_eq!(large.share, "74.6%");
+    }
+
+    #[test]
+    fn lists_the_top_senders_and_largest_messages_first() {
+        let report = StorageReport::new(
+            "a@example.com",
+            &messages(),
+            &classifier(),
+            &HashMap::new(),
+            NOW,
+        );
+
+        assert_eq!(report.senders.len(), TOP_SENDERS);
+        assert_eq!(report.senders[0].name, "s59@example.com");
+        assert_eq!(report.senders[0].share, "3.3%");
+        assert_eq!(report.senders[TOP_SENDERS - 1].name, "s35@example.com");
+
+        assert_eq!(report.largest.len(), TOP_MESSAGES);
+        let subjects: Vec<&str> = report.largest.iter().map(|m| m.subject.as_str()).collect();
+        let expected: Vec<String> = (10..60).rev().map(|i| format!("Message {}", i)).collect();
+        assert_eq!(subjects, expected);
+        assert_eq!(report.largest[0].size, "5.9 KB");
+        assert_eq!(report.largest[0].bucket, "large");
+        assert_eq!(
+            report.largest[0].date,
+            iso_date(NOW - 59 * 86_400_000)[..10]
+        );
+        assert!(report.largest[0].link.ends_with("#all/t59"));
+    }
+
+    #[test]
+    fn reports_no_share_without_messages() {
+        let report = StorageReport::new("a@example.com", &[], &classifier(), &HashMap::new(), NOW);
+        assert_eq!(report.total_size, "0.0 KB");
+        assert!(report
+            .buckets
+            .iter()
+            .all(|b| b.message_count == 0 && b.share == "0.0%"));
+        assert!(report.senders.is_empty());
+        assert!(report.largest.is_empty());
+    }
+}
@@ -0,0 +1,378 @@
+use crate::attachments::attachment_kind;
+use crate::cache::CachedMessage;
+use crate::stats::{label_display_name, sender_address};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+const DAY_MILLIS: i64 = 86_400_000;
+
+/// Longest age a rule can test for, about a century.
+const MAX_DAYS: i64 = 36_525;
+
+/// One test a message has to pass for a rule to apply.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum Condition {
+    /// Sender address matches a wildcard pattern such as `*@linkedin.com`.
+    Sender { pattern: String },
+    /// Message carries a label, given by ID or by name.
+    Label { label: String },
+    /// Message was received more than `days` days ago.
+    OlderThan { days: i64 },
+    /// Message was received within the last `days` days.
+    NewerThan { days: i64 },
+    /// Message size is at least `bytes`.
+    LargerThan { bytes: i64 },
+    /// Message size is below `bytes`.
+    SmallerThan { bytes: i64 },
+    /// Message has an attachment of a type such as "pdf" or "video", see
+    /// `attachment_kind`.
+    AttachmentKind { kind: String },
+    /// Subject matches a regular expression.
+    Subject { regex: String },
+}
+
+/// Puts messages passing all of `conditions` into `category`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub category: String,
+    pub conditions: Vec<Condition>,
+}
+
+/// Turns a wildcard pattern where `*` matches any text and `?` any single
+/// character into a case-insensitive regex matching the whole input.
+fn wildcard_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    let escaped = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+    RegexBuilder::new(&format!("^{}$", escaped))
+        .case_insensitive(true)
+        .build()
+}
+
+/// Returns the time `days` days before `now`, both in milliseconds since
+/// the epoch.
+fn days_before(now: i64, days: i64) -> Result<i64, String> {
+    if !(0..=MAX_DAYS).contains(&days) {
+        return Err(format!(
+            "Number of days must be between 0 and {}, not {}",
+            MAX_DAYS, days
+        ));
+    }
+    days.checked_mul(DAY_MILLIS)
+        .and_then(|age| now.checked_sub(age))
+        .ok_or_else(|| format!("{} days before now is out of range", days))
+}
+
+/// A condition with its patterns compiled.
+enum Test {
+    Sender(Regex),
+    Label(String),
+    ReceivedBefore(i64),
+    ReceivedAfter(i64),
+    LargerThan(i64),
+    SmallerThan(i64),
+    AttachmentKind(String),
+    Subject(Regex),
+}
+
+impl Test {
+    fn compile(condition: &Condition, now: i64) -> Result<Self, String> {
+        Ok(match condition {
+            Condition::Sender { pattern } => Test::Sender(
+                wildcard_regex(pattern)
+                    .map_err(|e| format!("Invalid sender pattern {}: {}", pattern, e))?,
+            ),
+            Condition::Label { label } => Test::Label(label.to_lowercase()),
+            Condition::OlderThan { days } => Test::ReceivedBefore(days_before(now, *days)?),
+            Condition::NewerThan { days } => Test::ReceivedAfter(days_before(now, *days)?),
+            Condition::LargerThan { bytes } => Test::LargerThan(*bytes),
+            Condition::SmallerThan { bytes } => Test::SmallerThan(*bytes),
+            Condition::AttachmentKind { kind } => Test::AttachmentKind(kind.to_lowercase()),
+            Condition::Subject { regex } => Test::Subject(
+                RegexBuilder::new(regex)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|e| format!("Invalid subject regex {}: {}", regex, e))?,
+            ),
+        })
+    }
+
+    fn matches(&self, message: &CachedMessage, label_names: &HashMap<String, String>) -> bool {
+        match self {
+            Test::Sender(pattern) => pattern.is_match(&sender_address(&message.sender)),
+            Test::Label(label) => message.labels.iter().any(|id| {
+                id.to_lowercase() == *label
+                    || label_display_name(id, label_names).to_lowercase() == *label
+            }),
+            Test::ReceivedBefore(time) => message.date < *time,
+            Test::ReceivedAfter(time) => message.date >= *time,
+            Test::LargerThan(bytes) => message.size_estimate as i64 >= *bytes,
+            Test::SmallerThan(bytes) => (message.size_estimate as i64) < *bytes,
+            Test::AttachmentKind(kind) => message
+                .attachments
+                .iter()
+                .any(|attachment| attachment_kind(attachment) == kind),
+            Test::Subject(regex) => regex.is_match(&message.subject),
+        }
+    }
+}
+
+/// Checks that every rule is named, has a category and at least one
+/// condition, and that its patterns and ages are valid.
+pub fn validate_rules(rules: &[Rule]) -> Result<(), String> {
+    for rule in rules {
+        if rule.name.trim().is_empty() {
+            return Err("Rule names cannot be empty".to_string());
+        }
+        if rule.category.trim().is_empty() {
+            return Err(format!("Rule {} has no category", rule.name));
+        }
+        if rule.conditions.is_empty() {
+            return Err(format!("Rule {} has no conditions", rule.name));
+        }
+        for condition in &rule.conditions {
+            Test::compile(condition, 0).map_err(|e| format!("Rule {}: {}", rule.name, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Assigns messages the category of the first rule they match.
+pub struct RuleEngine {
+    rules: Vec<(String, Vec<Test>)>,
+}
+
+impl RuleEngine {
+    /// Compiles `rules`, measuring message ages from `now` in milliseconds
+    /// since the epoch. Rules without conditions or with invalid ones are
+    /// skipped.
+    pub fn new(rules: &[Rule], now: i64) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| {
+                if rule.conditions.is_empty() {
+                    println!("rule error: rule {} has no conditions", rule.name);
+                    return None;
+                }
+                let tests: Result<Vec<Test>, String> = rule
+                    .conditions
+                    .iter()
+                    .map(|condition| Test::compile(condition, now))
+                    .collect();
+                match tests {
+                    Ok(tests) => Some((rule.category.clone(), tests)),
+                    Err(e) => {
+                        println!("rule error: {}", e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Returns the category of the first rule all of whose conditions
+    /// `message` passes.
+    pub fn categorize(
+        &self,
+        message: &CachedMessage,
+        label_names: &HashMap<String, String>,
+    ) -> Option<&str> {
+        self.rules
+            .iter()
+            .find(|(_, tests)| tests.iter().all(|test| test.matches(message, label_names)))
+            .map(|(category, _)| category.as_str())
+    }
+}
+
+/// Storage used by the messages assigned to one category.
+#[derive(Serialize)]
+pub struct CategoryTotal {
+    pub category: String,
+    pub message_count: usize,
+    pub total_size: i64,
+}
+
+/// Categorizes `messages` and returns the categories using the most
+/// storage first. Messages no rule matches are left out.
+pub fn categories_by_size(
+    messages: &[CachedMessage],
+    engine: &RuleEngine,
+    label_names: &HashMap<String, String>,
+) -> Vec<CategoryTotal> {
+    let mut categories: HashMap<&str, CategoryTotal> = HashMap::new();
+    for message in messages {
+        if let Some(category) = engine.categorize(message, label_names) {
+            let total = categories.entry(category).or_insert(CategoryTotal {
+                category: category.to_string(),
+                message_count: 0,
+                total_size: 0,
+            });
+            total.message_count += 1;
+            total.total_size += message.size_estimate as i64;
+        }
+    }
+    let mut totals: Vec<CategoryTotal> = categories.into_values().collect();
+    totals.sort_by_key(|total| Reverse(total.total_size));
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(conditions: Vec<Condition>) -> Rule {
+        Rule {
+            name: "old".to_string(),
+            category: "archive".to_string(),
+            conditions,
+        }
+    }
+
+    const NOW: i64 = 1_700_000_000_000;
+
+    fn message(sender: &str, labels: &[&str], age_days: i64) -> CachedMessage {
+        CachedMessage {
+            id: "1".to_string(),
+            thread_id: "1".to_string(),
+            subject: "Weekly digest".to_string(),
+            sender: sender.to_string(),
+            date: NOW - age_days * DAY_MILLIS,
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            size_estimate: 50_000,
+            attachments: vec![],
+            list_id: String::new(),
+            list_unsubscribe: String::new(),
+            list_unsubscribe_post: String::new(),
+            precedence: String::new(),
+        }
+    }
+
+    fn named(name: &str, category: &str, conditions: Vec<Condition>) -> Rule {
+        Rule {
+            name: name.to_string(),
+            category: category.to_string(),
+            conditions,
+        }
+    }
+
+    fn categorize(rules: &[Rule], message: &CachedMessage) -> Option<String> {
+        let label_names = HashMap::from([("Label_7".to_string(), "Receipts".to_string())]);
+        RuleEngine::new(rules, NOW)
+            .categorize(message, &label_names)
+            .map(str::to_string)
+    }
+
+    #[test]
+    fn matches_sender_wildcards() {
+        let rules = [named(
+            "linkedin",
+            "social",
+            vec![Condition::Sender {
+                pattern: "*@linkedin.com".to_string(),
+            }],
+        )];
+        let matching = message("LinkedIn <Messages-Noreply@LinkedIn.com>", &[], 1);
+        assert_eq!(categorize(&rules, &matching).as_deref(), Some("social"));
+        let other = message("news@linkedin.com.example.org", &[], 1);
+        assert_eq!(categorize(&rules, &other), None);
+    }
+
+    #[test]
+    fn matches_labels_by_name() {
+        let rules = [named(
+            "receipts",
+            "shopping",
+            vec![Condition::Label {
+                label: "receipts".to_string(),
+            }],
+        )];
+        let labelled = message("shop@example.com", &["INBOX", "Label_7"], 1);
+        assert_eq!(categorize(&rules, &labelled).as_deref(), Some("shopping"));
+        let unlabelled = message("shop@example.com", &["INBOX", "Label_8"], 1);
+        assert_eq!(categorize(&rules, &unlabelled), None);
+    }
+
+    #[test]
+    fn matches_message_age() {
+        let rules = [named(
+            "old",
+            "archive",
+            vec![Condition::OlderThan { days: 365 }],
+        )];
+        assert_eq!(
+            categorize(&rules, &message("a@example.com", &[], 400)).as_deref(),
+            Some("archive")
+        );
+        assert_eq!(categorize(&rules, &message("a@example.com", &[], 30)), None);
+
+        let rules = [named(
+            "recent",
+            "new",
+            vec![Condition::NewerThan { days: 7 }],
+        )];
+        assert_eq!(
+            categorize(&rules, &message("a@example.com", &[], 3)).as_deref(),
+            Some("new")
+        );
+        assert_eq!(categorize(&rules, &message("a@example.com", &[], 8)), None);
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = [
+            named(
+                "example",
+                "first",
+                vec![
+                    Condition::Sender {
+                        pattern: "*@example.com".to_string(),
+                    },
+                    Condition::LargerThan { bytes: 1_000_000 },
+                ],
+            ),
+            named(
+                "digest",
+                "second",
+                vec![Condition::Subject {
+                    regex: "digest$".to_string(),
+                }],
+            ),
+            named(
+                "any example",
+                "third",
+                vec![Condition::Sender {
+                    pattern: "*@example.com".to_string(),
+                }],
+            ),
+        ];
+        // The first rule fails on size, so the second one applies before
+        // the third
+        let small = message("a@example.com", &[], 1);
+        assert_eq!(categorize(&rules, &small).as_deref(), Some("second"));
+        let mut large = small.clone();
+        large.size_estimate = 2_000_000;
+        assert_eq!(categorize(&rules, &large).as_deref(), Some("first"));
+    }
+
+    #[test]
+    fn rejects_out_of_range_days() {
+        for days in [-1, MAX_DAYS + 1, i64::MAX / 1000, i64::MAX] {
+            assert!(validate_rules(&[rule(vec![Condition::OlderThan { days }])]).is_err());
+            assert!(validate_rules(&[rule(vec![Condition::NewerThan { days }])]).is_err());
+        }
+        assert!(validate_rules(&[rule(vec![Condition::OlderThan { days: 365 }])]).is_ok());
+        assert!(Test::compile(&Condition::OlderThan { days: MAX_DAYS }, i64::MIN).is_err());
+    }
+
+    #[test]
+    fn rejects_rules_without_conditions() {
+        assert!(validate_rules(&[rule(vec![])]).is_err());
+        let engine = RuleEngine::new(&[rule(vec![])], 0);
+        assert!(engine.rules.is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::gmail_client::{messages_list, MessageListEntry};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+/// Size thresholds queried in order by the largest-first strategy.
+/// Each tier is a plain `larger:` query, so every tier also returns the
+/// messages of the tiers above it; those are skipped by ID.
+const SIZE_TIERS: [&str; 4] = ["25M", "10M", "5M", "1M"];
+
+/// State of a largest-first scan which spans several `/api/summary` calls.
+pub struct LargestFirstScan {
+    tier: usize,
+    page_token: Option<String>,
+    seen: HashSet<String>,
+}
+
+impl LargestFirstScan {
+    pub fn new() -> Self {
+        Self {
+            tier: 0,
+            page_token: None,
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.tier >= SIZE_TIERS.len()
+    }
+
+    /// Returns up to `max` message entries which have not been returned
+    /// before, walking down the size tiers as each one is exhausted.
+    pub async fn next_batch(&mut self, token: &str, max: u32) -> Result<Vec<MessageListEntry>, ()> {
+        let mut batch = Vec::new();
+        while !self.is_done() && (batch.len() as u32) < max {
+            let query = format!("larger:{}", SIZE_TIERS[self.tier]);
+            let list = messages_list(
+                token,
+                max - batch.len() as u32,
+                self.page_token.as_deref(),
+                Some(&query),
+            )
+            .await?;
+
+            for entry in list.messages {
+                if self.seen.insert(entry.id.clone()) {
+                    batch.push(entry);
+                }
+            }
+
+            match list.nextPageToken {
+                Some(next_token) => self.page_token = Some(next_token),
+                None => {
+                    self.tier += 1;
+                    self.page_token = None;
+                }
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// Largest-first scans in progress, keyed by access token.
+pub struct ScanSessions {
+    scans: Mutex<HashMap<String, LargestFirstScan>>,
+}
+
+impl ScanSessions {
+    pub fn new() -> Self {
+        Self {
+            scans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes the scan for `token` out of the session map, starting a new one
+    /// if there is none. It must be handed back with `put` once done.
+    pub fn take(&self, token: &str) -> LargestFirstScan {
+        self.scans
+            .lock()
+            .unwrap()
+            .remove(token)
+            .unwrap_or_else(LargestFirstScan::new)
+    }
+
+    pub fn put(&self, token: &str, scan: LargestFirstScan) {
+        self.scans.lock().unwrap().insert(token.to_string(), scan);
+    }
+
+    pub fn reset(&self, token: &str) {
+        self.scans.lock().unwrap().remove(token);
+    }
+}
@@ -1,45 +1,80 @@
+use crate::attachments::{
+    inventory, save_attachments, totals_by_kind, AttachmentEntry, KindTotal, SaveReport,
+};
+use crate::cache::{CachedMessage, MessageCache};
+use crate::classifier::SizeClassifier;
+use crate::duplicates::{probable_duplicates, verify_duplicates, DuplicateReport};
+use crate::export::{to_csv, to_ndjson, ExportRow};
 use crate::gmail_client::*;
+use crate::journal::{JournalEntry, MessageChange};
+use crate::mail_archive::{archive_messages, ArchiveFormat, ArchiveReport};
+use crate::mime_strip::{AttachmentStripper, RemovedPart};
+use crate::newsletters::{lists_by_size, ListTotal};
+use crate::report::StorageReport;
+use crate::rules::{categories_by_size, validate_rules, CategoryTotal, Rule, RuleEngine};
+use crate::scan::ScanSessions;
+use crate::settings::{local_folder, SettingsStore};
+use crate::snapshot::{Snapshot, SnapshotDiff};
+use crate::stats::{
+    labels_by_size, sender_key, senders_by_size, size_by_age, threads_by_size, AgeTotal,
+    LabelTotal, SenderTotal, ThreadTotal,
+};
+use crate::sync::{sync, SyncReport};
+use crate::unsubscribe::{
+    choose_method, one_click_unsubscribe, UnsubscribeMethod, UnsubscribePlan,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use handlebars::Handlebars;
 use reqwest;
 use rocket::http::{ContentType, Cookie, CookieJar, SameSite};
 use rocket::response::content::{RawHtml, RawJavaScript};
+use rocket::response::status::BadRequest;
 use rocket::serde::json::serde_json;
 use rocket::time::Duration;
-use rocket::{get, response::Redirect, serde::json::Json, State};
+use rocket::{get, post, response::Redirect, serde::json::Json, State};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 use urlencoding;
 
 #[derive(Serialize)]
 pub struct SearchResult {
+    id: String,
     title: String,
+    sender: String,
     size: i32,
+    bucket: String,
     thread_id: String,
 }
 
 #[derive(Deserialize)]
-struct TokenResponse {
-    access_token: String,
-    expires_in: i32,
-    scope: String,
+pub(crate) struct TokenResponse {
+    pub(crate) access_token: String,
+    pub(crate) expires_in: i32,
+    pub(crate) scope: String,
     token_type: String,
+    /// Only sent when offline access was requested.
+    pub(crate) refresh_token: Option<String>,
 }
 
 #[derive(Deserialize)]
-struct CredentialsWeb {
-    client_id: String,
+pub(crate) struct CredentialsWeb {
+    pub(crate) client_id: String,
     project_id: String,
     auth_uri: String,
-    token_uri: String,
+    pub(crate) token_uri: String,
     auth_provider_x509_cert_url: String,
-    client_secret: String,
-    redirect_uris: Vec<String>,
+    pub(crate) client_secret: String,
+    pub(crate) redirect_uris: Vec<String>,
     javascript_origins: Vec<String>,
 }
 
 #[derive(Deserialize)]
-struct Credentials {
-    web: CredentialsWeb,
+pub(crate) struct Credentials {
+    pub(crate) web: CredentialsWeb,
 }
 
 #[get("/")]
@@ -74,30 +109,113 @@ pub fn home(cookies: &CookieJar<'_>, hbs: &State<Handlebars<'static>>) -> RawHtm
     }
 }
 
+/// Renders a standalone storage report of the scanned messages, meant to
+/// be printed or saved as an HTML file and shared.
+#[get("/report")]
+pub async fn report(
+    cookies: &CookieJar<'_>,
+    hbs: &State<Handlebars<'static>>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> RawHtml<String> {
+    let account = match cookies.get_private("token") {
+        Some(token) => account_email(token.value(), cookies)
+            .await
+            .map(|account| (token, account)),
+        None => None,
+    };
+    let html = match account {
+        Some((token, account)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0);
+            let report = StorageReport::new(
+                &account,
+                &cache.all(&account),
+                &settings.size_classifier(),
+                &label_names(token.value()).await,
+                now,
+            );
+            hbs.render("report", &report)
+        }
+        None => hbs.render(
+            "error",
+            &json!({
+                "error": "Not logged in",
+                "redirect": "/login"
+            }),
+        ),
+    };
+    RawHtml(html.unwrap_or_else(|e| format!("Template error: {}", e)))
+}
+
 #[get("/js/main.js")]
 pub fn mainjs() -> RawJavaScript<String> {
     let js = include_str!("../static/js/main.js");
     RawJavaScript(js.to_string())
 }
 
+#[get("/js/age_section.js")]
+pub fn age_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/age_section.js");
+    RawJavaScript(js.to_string())
+}
+
 #[get("/js/app.js")]
 pub fn appjs() -> RawJavaScript<String> {
     let js = include_str!("../static/js/app.js");
     RawJavaScript(js.to_string())
 }
 
+#[get("/js/attachment_section.js")]
+pub fn attachment_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/attachment_section.js");
+    RawJavaScript(js.to_string())
+}
+
 #[get("/js/chart_section.js")]
 pub fn chart_sectionjs() -> RawJavaScript<String> {
     let js = include_str!("../static/js/chart_section.js");
     RawJavaScript(js.to_string())
 }
 
+#[get("/js/cleanup_section.js")]
+pub fn cleanup_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/cleanup_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/filter_section.js")]
+pub fn filter_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/filter_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/duplicate_section.js")]
+pub fn duplicate_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/duplicate_section.js");
+    RawJavaScript(js.to_string())
+}
+
 #[get("/js/header.js")]
 pub fn headerjs() -> RawJavaScript<String> {
     let js = include_str!("../static/js/header.js");
     RawJavaScript(js.to_string())
 }
 
+#[get("/js/newsletter_section.js")]
+pub fn newsletter_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/newsletter_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/rule_section.js")]
+pub fn rule_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/rule_section.js");
+    RawJavaScript(js.to_string())
+}
+
 #[get("/js/search.js")]
 pub fn searchjs() -> RawJavaScript<String> {
     let js = include_str!("../static/js/search.js");
@@ -110,6 +228,36 @@ pub fn tab_sectionjs() -> RawJavaScript<String> {
     RawJavaScript(js.to_string())
 }
 
+#[get("/js/label_section.js")]
+pub fn label_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/label_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/insights_section.js")]
+pub fn insights_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/insights_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/sender_section.js")]
+pub fn sender_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/sender_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/thread_section.js")]
+pub fn thread_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/thread_section.js");
+    RawJavaScript(js.to_string())
+}
+
+#[get("/js/trend_section.js")]
+pub fn trend_sectionjs() -> RawJavaScript<String> {
+    let js = include_str!("../static/js/trend_section.js");
+    RawJavaScript(js.to_string())
+}
+
 #[get("/js/utils.js")]
 pub fn utilsjs() -> RawJavaScript<String> {
     let js = include_str!("../static/js/utils.js");
@@ -122,22 +270,68 @@ pub fn logo() -> (ContentType, &'static [u8]) {
     (ContentType::PNG, img.as_slice())
 }
 
-#[get("/login")]
-pub fn login(cookies: &CookieJar<'_>) -> Redirect {
+pub(crate) const READONLY_SCOPE: &str = "https://www.googleapis.com/auth/gmail.readonly";
+pub(crate) const MODIFY_SCOPE: &str = "https://www.googleapis.com/auth/gmail.modify";
+pub(crate) const SETTINGS_SCOPE: &str = "https://www.googleapis.com/auth/gmail.settings.basic";
+
+/// Returns `len` random bytes from the operating system, encoded for URLs.
+pub(crate) fn random_string(len: usize) -> Result<String, String> {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Could not get random bytes: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Starts the OAuth flow. By default only read access is requested;
+/// `mode=modify` asks for the gmail.modify scope needed to clean up.
+/// `mode=filters` also asks for the gmail.settings.basic scope needed to
+/// create filters, along with gmail.modify for the labels they apply.
+#[get("/login?<mode>")]
+pub fn login(
+    mode: Option<String>,
+    cookies: &CookieJar<'_>,
+    scans: &State<ScanSessions>,
+) -> Redirect {
+    if let Some(token) = cookies.get_private("token") {
+        scans.reset(token.value());
+    }
     cookies.remove_private("token");
+    cookies.remove_private("email");
     cookies.remove_private("page_token");
+    cookies.remove_private("scope");
+    // The state ties the callback to this browser, so that nobody else can
+    // sign it in to their account
+    let state = match random_string(16) {
+        Ok(state) => state,
+        Err(e) => {
+            println!("sign in error: {}", e);
+            return Redirect::to("/error");
+        }
+    };
+    cookies.add_private(
+        Cookie::build(("oauth_state", state.clone()))
+            .max_age(Duration::minutes(10))
+            .same_site(SameSite::Lax),
+    );
     let credentials_file = include_str!("../credentials.json");
     match serde_json::from_str::<Credentials>(credentials_file) {
         Ok(credentials) => {
-            let scope = urlencoding::encode("https://www.googleapis.com/auth/gmail.readonly");
+            let scope = match mode.as_deref() {
+                Some("modify") => urlencoding::encode(MODIFY_SCOPE).into_owned(),
+                Some("filters") => {
+                    urlencoding::encode(&format!("{} {}", MODIFY_SCOPE, SETTINGS_SCOPE))
+                        .into_owned()
+                }
+                _ => urlencoding::encode(READONLY_SCOPE).into_owned(),
+            };
             let redirect_uri = urlencoding::encode(&credentials.web.redirect_uris[0]);
             let client_id = credentials.web.client_id;
 
             let auth_url = format!(
-                "https://accounts.google.com/o/oauth2/v2/auth?scope={}&redirect_uri={}&response_type=code&client_id={}", 
+                "https://accounts.google.com/o/oauth2/v2/auth?scope={}&redirect_uri={}&response_type=code&client_id={}&state={}", 
                 scope,
                 redirect_uri,
-                client_id
+                client_id,
+                state
             );
 
             Redirect::to(auth_url)
@@ -146,15 +340,24 @@ pub fn login(cookies: &CookieJar<'_>) -> Redirect {
     }
 }
 
-#[get("/oauth2callback?<code>&<_state>&<scope>&<_authuser>&<_prompt>")]
+#[get("/oauth2callback?<code>&<state>&<scope>&<_authuser>&<_prompt>")]
 pub async fn oauth2_callback(
     code: Option<String>,
-    _state: Option<String>,
+    state: Option<String>,
     scope: Option<String>,
     _authuser: Option<String>,
     _prompt: Option<String>,
     cookies: &CookieJar<'_>,
 ) -> Redirect {
+    // Only accept the callback of a sign in started by this browser
+    let expected = cookies
+        .get_private("oauth_state")
+        .map(|cookie| cookie.value().to_string());
+    cookies.remove_private("oauth_state");
+    if expected.is_none() || state != expected {
+        println!("sign in error: the state does not match");
+        return Redirect::to("/error");
+    }
     match code {
         Some(code) => {
             let credentials_file = include_str!("../credentials.json");
@@ -187,6 +390,13 @@ pub async fn oauth2_callback(
                                                 ))
                                                 .same_site(SameSite::Lax);
                                         cookies.add_private(cookie);
+                                        cookies.add_private(
+                                            Cookie::build((
+                                                "scope",
+                                                scope.unwrap_or(token_data.scope),
+                                            ))
+                                            .same_site(SameSite::Lax),
+                                        );
                                         Redirect::to("/home")
                                     }
                                     Err(e) => Redirect::to("/error"),
@@ -223,41 +433,159 @@ pub fn error(hbs: &State<Handlebars<'static>>) -> RawHtml<String> {
     RawHtml(html)
 }
 
-#[get("/api/summary?<max>")]
-pub async fn summary(max: String, cookies: &CookieJar<'_>) -> Json<Vec<SearchResult>> {
+impl SearchResult {
+    fn from_cached(message: &CachedMessage, classifier: &SizeClassifier) -> Self {
+        Self {
+            id: message.id.clone(),
+            title: message.subject.clone(),
+            sender: message.sender.clone(),
+            size: message.size_estimate,
+            bucket: classifier
+                .classify(message.size_estimate as i64)
+                .to_string(),
+            thread_id: message.thread_id.clone(),
+        }
+    }
+}
+
+/// Returns the email address of the signed in account, which keys the
+/// local message cache. It is remembered in a private cookie after the
+/// first lookup.
+async fn account_email(token: &str, cookies: &CookieJar<'_>) -> Option<String> {
+    if let Some(email) = cookies.get_private("email") {
+        return Some(email.value().to_string());
+    }
+    let user = user_get(token).await.ok()?;
+    cookies.add_private(Cookie::build(("email", user.email.clone())).same_site(SameSite::Lax));
+    Some(user.email)
+}
+
+/// Looks up each listed message in the cache, fetching and caching the
+/// ones which have not been seen before. Messages which could not be
+/// retrieved are skipped.
+async fn fetch_results(
+    token: &str,
+    account: &str,
+    cache: &MessageCache,
+    classifier: &SizeClassifier,
+    entries: Vec<MessageListEntry>,
+) -> Vec<SearchResult> {
+    let mut results = Vec::new();
+    for entry in entries {
+        if let Some(cached) = cache.get(account, &entry.id) {
+            results.push(SearchResult::from_cached(&cached, classifier));
+        } else if let Ok(msg) = message_get(token, &entry.id).await {
+            let cached = CachedMessage::from_message(msg);
+            cache.put(account, &cached);
+            results.push(SearchResult::from_cached(&cached, classifier));
+        }
+    }
+    results
+}
+
+/// Returns whether the user granted `scope` at sign in.
+fn has_scope(cookies: &CookieJar<'_>, scope: &str) -> bool {
+    cookies
+        .get_private("scope")
+        .map(|granted| granted.value().split(' ').any(|s| s == scope))
+        .unwrap_or(false)
+}
+
+/// Returns whether the user granted the gmail.modify scope at sign in.
+fn can_modify(cookies: &CookieJar<'_>) -> bool {
+    has_scope(cookies, MODIFY_SCOPE)
+}
+
+/// Maps the IDs of the user's labels to their names.
+async fn label_names(token: &str) -> HashMap<String, String> {
+    labels_list(token)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|label| (label.id, label.name))
+        .collect()
+}
+
+/// Compiles the saved classification rules, measuring ages from now.
+fn rule_engine(settings: &SettingsStore) -> RuleEngine {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    RuleEngine::new(&settings.rules(), now)
+}
+
+/// Records the storage totals of the account once a scan has completed.
+async fn record_snapshot(
+    token: &str,
+    account: &str,
+    cache: &MessageCache,
+    classifier: &SizeClassifier,
+) {
+    match profile_get(token).await {
+        Ok(profile) => {
+            let snapshot = Snapshot::take(&cache.all(account), profile.messagesTotal, classifier);
+            cache.add_snapshot(account, &snapshot);
+        }
+        Err(_) => println!("failed to get profile for snapshot"),
+    }
+}
+
+#[get("/api/summary?<max>&<strategy>")]
+pub async fn summary(
+    max: String,
+    strategy: Option<String>,
+    cookies: &CookieJar<'_>,
+    scans: &State<ScanSessions>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Json<Vec<SearchResult>> {
     match cookies.get_private("token") {
         Some(token) => {
+            let account = match account_email(token.value(), cookies).await {
+                Some(account) => account,
+                None => return Json(vec![]),
+            };
+            let classifier = settings.size_classifier();
             let max_results: u32 = max.parse().unwrap_or(10);
+            if strategy.as_deref() == Some("largest") {
+                let mut scan = scans.take(token.value());
+                let was_done = scan.is_done();
+                let entries = scan.next_batch(token.value(), max_results).await;
+                let finished = !was_done && scan.is_done();
+                scans.put(token.value(), scan);
+                return match entries {
+                    Ok(entries) => {
+                        let results =
+                            fetch_results(token.value(), &account, cache, &classifier, entries)
+                                .await;
+                        if finished {
+                            record_snapshot(token.value(), &account, cache, &classifier).await;
+                        }
+                        Json(results)
+                    }
+                    Err(_) => Json(vec![]),
+                };
+            }
+
             let page_token = cookies
                 .get_private("page_token")
                 .map(|c| c.value().to_string());
-            match messages_list(token.value(), max_results, page_token.as_deref()).await {
+            match messages_list(token.value(), max_results, page_token.as_deref(), None).await {
                 Ok(res) => {
-                    let mut results = Vec::new();
-                    for message in res.messages {
-                        if let Ok(msg) = message_get(token.value(), &message.id).await {
-                            results.push(SearchResult {
-                                title: msg
-                                    .payload
-                                    .unwrap()
-                                    .headers
-                                    .into_iter()
-                                    .find(|h| h.name == "Subject")
-                                    .unwrap()
-                                    .value,
-                                size: msg.sizeEstimate,
-                                thread_id: msg.threadId,
-                            });
-                        }
-                    }
+                    let results =
+                        fetch_results(token.value(), &account, cache, &classifier, res.messages)
+                            .await;
                     // Set the next page token as a private cookie
                     if let Some(next_token) = res.nextPageToken {
                         let page_cookie =
                             Cookie::build(("page_token", next_token)).same_site(SameSite::Lax);
                         cookies.add_private(page_cookie);
                     } else {
-                        // If no next page, remove the cookie
+                        // If no next page, remove the cookie and record the
+                        // totals of the completed scan
                         cookies.remove_private("page_token");
+                        record_snapshot(token.value(), &account, cache, &classifier).await;
                     }
                     Json(results)
                 }
@@ -268,6 +596,1488 @@ pub async fn summary(max: String, cookies: &CookieJar<'_>) -> Json<Vec<SearchRes
     }
 }
 
+/// Returns every cached message of the signed in account.
+#[get("/api/messages")]
+pub async fn messages(
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Json<Vec<SearchResult>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let classifier = settings.size_classifier();
+                Json(
+                    cache
+                        .all(&account)
+                        .iter()
+                        .map(|m| SearchResult::from_cached(m, &classifier))
+                        .collect(),
+                )
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Exports every cached message of the signed in account for analysis
+/// elsewhere. `format` is one of "csv", "json" (the default) or "ndjson".
+#[get("/api/export?<format>")]
+pub async fn export(
+    format: Option<String>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Result<(ContentType, String), BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    let classifier = settings.size_classifier();
+    let names = label_names(token.value()).await;
+    let mut messages = cache.all(&account);
+    messages.sort_by_key(|message| Reverse(message.date));
+    let rows: Vec<ExportRow> = messages
+        .iter()
+        .map(|m| ExportRow::new(m, &classifier, &names))
+        .collect();
+    match format.as_deref().unwrap_or("json") {
+        "csv" => Ok((ContentType::CSV, to_csv(&rows))),
+        "ndjson" => Ok((
+            ContentType::new("application", "x-ndjson"),
+            to_ndjson(&rows),
+        )),
+        "json" => serde_json::to_string(&rows)
+            .map(|json| (ContentType::JSON, json))
+            .map_err(|e| BadRequest(format!("Could not export: {}", e))),
+        format => Err(BadRequest(format!("Unknown export format {}", format))),
+    }
+}
+
+/// Brings the local cache up to date with the mailbox, incrementally
+/// when possible.
+#[post("/api/sync")]
+pub async fn sync_mailbox(
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Json<Option<SyncReport>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let classifier = settings.size_classifier();
+                let report = sync(token.value(), &account, cache).await.ok();
+                if report.is_some() {
+                    record_snapshot(token.value(), &account, cache, &classifier).await;
+                }
+                Json(report)
+            }
+            None => Json(None),
+        },
+        None => Json(None),
+    }
+}
+
+/// Returns the storage snapshots of the signed in account, oldest first.
+#[get("/api/snapshots")]
+pub async fn snapshots(
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<Snapshot>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => Json(cache.snapshots(&account)),
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Returns the change in storage between two snapshots.
+#[get("/api/snapshots/diff?<from>&<to>")]
+pub async fn snapshots_diff(
+    from: i64,
+    to: i64,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Option<SnapshotDiff>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let snapshots = cache.snapshots(&account);
+                let from = snapshots.iter().find(|s| s.id == from);
+                let to = snapshots.iter().find(|s| s.id == to);
+                match (from, to) {
+                    (Some(from), Some(to)) => Json(Some(SnapshotDiff::between(from, to))),
+                    _ => Json(None),
+                }
+            }
+            None => Json(None),
+        },
+        None => Json(None),
+    }
+}
+
+/// Ranks the threads of the scanned messages by total size.
+#[get("/api/threads?<limit>")]
+pub async fn threads(
+    limit: Option<usize>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<ThreadTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let mut totals = threads_by_size(&cache.all(&account));
+                totals.truncate(limit.unwrap_or(50));
+                Json(totals)
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Returns the size of a whole thread, including messages which have not
+/// been scanned yet.
+#[get("/api/threads/<id>")]
+pub async fn thread_detail(id: &str, cookies: &CookieJar<'_>) -> Json<Option<ThreadTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match threads_get(token.value(), id).await {
+            Ok(thread) => {
+                let messages: Vec<CachedMessage> = thread
+                    .messages
+                    .into_iter()
+                    .map(CachedMessage::from_message)
+                    .collect();
+                Json(threads_by_size(&messages).into_iter().next())
+            }
+            Err(_) => Json(None),
+        },
+        None => Json(None),
+    }
+}
+
+/// Ranks senders of the scanned messages by total size. `group` is either
+/// "address" (the default) or "domain".
+#[get("/api/senders?<group>&<limit>")]
+pub async fn senders(
+    group: Option<String>,
+    limit: Option<usize>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<SenderTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let by_domain = group.as_deref() == Some("domain");
+                let mut totals = senders_by_size(&cache.all(&account), by_domain);
+                totals.truncate(limit.unwrap_or(50));
+                Json(totals)
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Returns the scanned messages of one sender address or domain, largest
+/// first.
+#[get("/api/senders/messages?<sender>&<group>")]
+pub async fn sender_messages(
+    sender: String,
+    group: Option<String>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Json<Vec<SearchResult>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let by_domain = group.as_deref() == Some("domain");
+                let mut messages: Vec<CachedMessage> = cache
+                    .all(&account)
+                    .into_iter()
+                    .filter(|m| sender_key(&m.sender, by_domain) == sender)
+                    .collect();
+                messages.sort_by_key(|message| Reverse(message.size_estimate));
+                let classifier = settings.size_classifier();
+                Json(
+                    messages
+                        .iter()
+                        .map(|m| SearchResult::from_cached(m, &classifier))
+                        .collect(),
+                )
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Lists the attachments of the scanned messages. `sort` is one of "size"
+/// (the default), "name" or "type"; `kind` restricts the list to one
+/// attachment type such as "pdf" or "video".
+#[get("/api/attachments?<sort>&<kind>&<limit>")]
+pub async fn attachments(
+    sort: Option<String>,
+    kind: Option<String>,
+    limit: Option<usize>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<AttachmentEntry>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let mut entries =
+                    inventory(&cache.all(&account), sort.as_deref().unwrap_or("size"));
+                if let Some(kind) = kind {
+                    entries.retain(|entry| entry.kind == kind);
+                }
+                entries.truncate(limit.unwrap_or(200));
+                Json(entries)
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Totals the attachments of the scanned messages per type.
+#[get("/api/attachments/types")]
+pub async fn attachment_types(
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<KindTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => Json(totals_by_kind(&inventory(&cache.all(&account), "size"))),
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SelectedAttachment {
+    message_id: String,
+    part_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct SaveAttachmentsRequest {
+    attachments: Vec<SelectedAttachment>,
+    /// Local folder to save to, the Downloads folder if not given.
+    folder: Option<String>,
+}
+
+/// Saves the selected attachments to a local folder along with a manifest
+/// of where each came from, so that their messages can be trashed safely.
+#[post("/api/attachments/save", data = "<request>")]
+pub async fn download_attachments(
+    request: Json<SaveAttachmentsRequest>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<SaveReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not load the profile".to_string()))?;
+    if request.attachments.is_empty() {
+        return Err(BadRequest("No attachments selected".to_string()));
+    }
+    let folder =
+        local_folder(request.folder.as_deref(), "Gmail Cleaner Attachments").map_err(BadRequest)?;
+    let selected: Vec<(String, String)> = request
+        .attachments
+        .iter()
+        .map(|item| (item.message_id.clone(), item.part_id.clone()))
+        .collect();
+    Ok(Json(
+        save_attachments(token.value(), &cache.all(&account), &selected, &folder).await,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct StripRequest {
+    attachments: Vec<SelectedAttachment>,
+    /// Save the attachments to `folder` before removing them.
+    #[serde(default)]
+    save: bool,
+    folder: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct StrippedMessage {
+    original_id: String,
+    new_id: String,
+    removed: Vec<RemovedPart>,
+}
+
+#[derive(Serialize)]
+pub struct StripReport {
+    stripped: Vec<StrippedMessage>,
+    /// Messages in which none of the selected attachments were found.
+    unchanged: Vec<String>,
+    failed: Vec<String>,
+}
+
+/// Removes the selected attachments from their messages while keeping the
+/// messages. Gmail cannot edit messages, so each one is rebuilt with the
+/// attachments replaced by a note, inserted with the original labels and
+/// date, and the original is moved to the Trash. With `save` set the
+/// attachments are saved locally first and a message is left alone if
+/// that fails.
+#[post("/api/attachments/strip", data = "<request>")]
+pub async fn strip_attachments(
+    request: Json<StripRequest>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<StripReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !can_modify(cookies) {
+        return Err(BadRequest(
+            "Removing attachments needs permission to modify Gmail, sign in again to grant it"
+                .to_string(),
+        ));
+    }
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    let folder = if request.save {
+        Some(
+            local_folder(request.folder.as_deref(), "Gmail Cleaner Attachments")
+                .map_err(BadRequest)?,
+        )
+    } else {
+        None
+    };
+
+    let mut part_ids: Vec<(String, HashSet<String>)> = vec![];
+    for item in &request.attachments {
+        match part_ids.iter_mut().find(|(id, _)| *id == item.message_id) {
+            Some((_, parts)) => {
+                parts.insert(item.part_id.clone());
+            }
+            None => part_ids.push((
+                item.message_id.clone(),
+                HashSet::from([item.part_id.clone()]),
+            )),
+        }
+    }
+
+    let mut report = StripReport {
+        stripped: vec![],
+        unchanged: vec![],
+        failed: vec![],
+    };
+    let mut entry = JournalEntry::new("strip_attachments");
+    for (message_id, parts) in part_ids {
+        let Some(cached) = cache.get(&account, &message_id) else {
+            report.failed.push(message_id);
+            continue;
+        };
+        // Drafts would turn into sent looking messages
+        if cached.labels.iter().any(|label| label == "DRAFT") {
+            report.failed.push(message_id);
+            continue;
+        }
+
+        let mut saved_paths = HashMap::new();
+        if let Some(folder) = &folder {
+            let selected: Vec<(String, String)> = parts
+                .iter()
+                .map(|part_id| (message_id.clone(), part_id.clone()))
+                .collect();
+            let saved = save_attachments(
+                token.value(),
+                std::slice::from_ref(&cached),
+                &selected,
+                folder,
+            )
+            .await;
+            if !saved.failed.is_empty() {
+                report.failed.push(message_id);
+                continue;
+            }
+            for attachment in saved.saved {
+                saved_paths.insert(attachment.part_id, attachment.path);
+            }
+        }
+
+        let original = match message_get_raw(token.value(), &message_id).await {
+            Ok(original) => original,
+            Err(()) => {
+                report.failed.push(message_id);
+                continue;
+            }
+        };
+        let Some(Ok(raw)) = original.raw.as_deref().map(decode_base64url) else {
+            report.failed.push(message_id);
+            continue;
+        };
+        let (stripped, removed) = AttachmentStripper::new(parts, saved_paths).strip(&raw);
+        if removed.is_empty() {
+            report.unchanged.push(message_id);
+            continue;
+        }
+
+        let copy = match messages_insert(
+            token.value(),
+            &stripped,
+            &original.labelIds,
+            &original.threadId,
+        )
+        .await
+        {
+            Ok(copy) => copy,
+            Err(()) => {
+                report.failed.push(message_id);
+                continue;
+            }
+        };
+        if messages_trash(token.value(), &message_id).await.is_err() {
+            // Do not leave both copies in the mailbox
+            let _ = messages_trash(token.value(), &copy.id).await;
+            report.failed.push(message_id);
+            continue;
+        }
+        entry.changes.push(MessageChange::from_labels(
+            &message_id,
+            &original.labelIds,
+            &["TRASH"],
+            &[],
+        ));
+        entry.created_ids.push(copy.id.clone());
+        cache.remove(&account, &message_id);
+        if let Ok(msg) = message_get(token.value(), &copy.id).await {
+            cache.put(&account, &CachedMessage::from_message(msg));
+        }
+        report.stripped.push(StrippedMessage {
+            original_id: message_id,
+            new_id: copy.id,
+            removed,
+        });
+    }
+    if !entry.is_empty() {
+        cache.add_journal_entry(&account, &entry);
+    }
+    Ok(Json(report))
+}
+
+/// Reports attachments which appear more than once in the scanned
+/// messages. With `verify` set, the largest `limit` groups are downloaded
+/// and compared by content.
+#[get("/api/duplicates?<verify>&<limit>")]
+pub async fn duplicates(
+    verify: Option<bool>,
+    limit: Option<usize>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<DuplicateReport> {
+    let empty = DuplicateReport {
+        groups: vec![],
+        redundant_size: 0,
+    };
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let report = probable_duplicates(&cache.all(&account));
+                if verify.unwrap_or(false) {
+                    Json(verify_duplicates(token.value(), report, limit.unwrap_or(10)).await)
+                } else {
+                    Json(report)
+                }
+            }
+            None => Json(empty),
+        },
+        None => Json(empty),
+    }
+}
+
+/// Breaks down the storage of the scanned messages by label.
+#[get("/api/labels")]
+pub async fn labels(cookies: &CookieJar<'_>, cache: &State<MessageCache>) -> Json<Vec<LabelTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let names = label_names(token.value()).await;
+                Json(labels_by_size(&cache.all(&account), &names))
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Histogram of the storage of the scanned messages by the month they were
+/// received in, or by year if `period` is "year".
+#[get("/api/age?<period>")]
+pub async fn age(
+    period: Option<String>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<AgeTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let by_year = period.as_deref() == Some("year");
+                Json(size_by_age(&cache.all(&account), by_year))
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Reports the mailing lists and marketing senders among the scanned
+/// messages with the storage each one uses.
+#[get("/api/newsletters?<limit>")]
+pub async fn newsletters(
+    limit: Option<usize>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<ListTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let mut lists = lists_by_size(&cache.all(&account));
+                lists.truncate(limit.unwrap_or(100));
+                Json(lists)
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Works out how to unsubscribe from a list found in the scanned
+/// messages.
+fn unsubscribe_plan(cache: &MessageCache, account: &str, list_id: &str) -> Option<UnsubscribePlan> {
+    let list = lists_by_size(&cache.all(account))
+        .into_iter()
+        .find(|list| list.list_id == list_id)?;
+    let (method, url) = choose_method(&list.unsubscribe, &list.unsubscribe_post);
+    Some(UnsubscribePlan {
+        list_id: list.list_id,
+        name: list.name,
+        method,
+        url,
+    })
+}
+
+/// Shows what unsubscribing from a list would do, for the user to confirm.
+#[get("/api/unsubscribe?<list_id>")]
+pub async fn unsubscribe_preview(
+    list_id: String,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Option<UnsubscribePlan>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => Json(unsubscribe_plan(cache, &account, &list_id)),
+            None => Json(None),
+        },
+        None => Json(None),
+    }
+}
+
+/// Unsubscribes from a list once the user has confirmed. One-click lists
+/// are unsubscribed from here; for the others the link or mailto URI is
+/// returned for the browser to open.
+#[post("/api/unsubscribe?<list_id>")]
+pub async fn unsubscribe(
+    list_id: String,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<UnsubscribePlan>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    let plan = unsubscribe_plan(cache, &account, &list_id)
+        .ok_or_else(|| BadRequest(format!("No list {} in the scanned messages", list_id)))?;
+    match plan.method {
+        UnsubscribeMethod::OneClick => match one_click_unsubscribe(&plan.url).await {
+            Ok(_) => Ok(Json(plan)),
+            Err(_) => Err(BadRequest(
+                "The list did not accept the unsubscribe request".to_string(),
+            )),
+        },
+        UnsubscribeMethod::None => Err(BadRequest(
+            "The list does not say how to unsubscribe".to_string(),
+        )),
+        _ => Ok(Json(plan)),
+    }
+}
+
+#[derive(Serialize)]
+pub struct Permissions {
+    modify: bool,
+    filters: bool,
+}
+
+/// Tells the UI whether cleanup actions are available.
+#[get("/api/permissions")]
+pub fn permissions(cookies: &CookieJar<'_>) -> Json<Permissions> {
+    Json(Permissions {
+        modify: can_modify(cookies),
+        filters: has_scope(cookies, SETTINGS_SCOPE) && can_modify(cookies),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct SelectedItems {
+    #[serde(default)]
+    message_ids: Vec<String>,
+    #[serde(default)]
+    thread_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct TrashReport {
+    trashed: Vec<String>,
+    failed: Vec<String>,
+}
+
+/// Moves the selected messages and threads to the Trash and drops them
+/// from the local cache. Needs the gmail.modify scope.
+#[post("/api/trash", data = "<request>")]
+pub async fn trash(
+    request: Json<SelectedItems>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<TrashReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !can_modify(cookies) {
+        return Err(BadRequest(
+            "Moving mail to the Trash needs permission to modify Gmail, sign in again to grant it"
+                .to_string(),
+        ));
+    }
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    let mut report = TrashReport {
+        trashed: vec![],
+        failed: vec![],
+    };
+    let mut entry = JournalEntry::new("trash");
+    for id in &request.message_ids {
+        let cached = cache.get(&account, id);
+        match messages_trash(token.value(), id).await {
+            Ok(()) => {
+                entry.changes.push(match cached {
+                    Some(message) => MessageChange::new(&message, &["TRASH"], &[]),
+                    None => MessageChange {
+                        id: id.clone(),
+                        labels_before: vec![],
+                        labels_after: vec!["TRASH".to_string()],
+                    },
+                });
+                cache.remove(&account, id);
+                report.trashed.push(id.clone());
+            }
+            Err(()) => report.failed.push(id.clone()),
+        }
+    }
+    for id in &request.thread_ids {
+        match threads_trash(token.value(), id).await {
+            Ok(()) => {
+                entry.thread_ids.push(id.clone());
+                cache.remove_thread(&account, id);
+                report.trashed.push(id.clone());
+            }
+            Err(()) => report.failed.push(id.clone()),
+        }
+    }
+    if !entry.is_empty() {
+        cache.add_journal_entry(&account, &entry);
+    }
+    Ok(Json(report))
+}
+
+#[derive(Serialize)]
+pub struct ModifyReport {
+    modified: Vec<String>,
+    failed: Vec<String>,
+}
+
+/// Adds and removes labels on the selected messages and threads, keeping
+/// the cache up to date and journalling the change under `action`.
+async fn modify_selected(
+    token: &str,
+    account: &str,
+    cache: &MessageCache,
+    items: &SelectedItems,
+    add: &[&str],
+    remove: &[&str],
+    action: &str,
+) -> ModifyReport {
+    let mut report = ModifyReport {
+        modified: vec![],
+        failed: vec![],
+    };
+    let mut entry = JournalEntry::new(action);
+    for id in &items.message_ids {
+        // Without the labels beforehand the change could not be undone, so
+        // the message is left alone
+        let cached = cache.get(account, id);
+        let labels = match &cached {
+            Some(message) => message.labels.clone(),
+            None => match message_get(token, id).await {
+                Ok(msg) => msg.labelIds,
+                Err(()) => {
+                    report.failed.push(id.clone());
+                    continue;
+                }
+            },
+        };
+        match messages_modify(token, id, add, remove).await {
+            Ok(()) => {
+                let change = MessageChange::from_labels(id, &labels, add, remove);
+                if let Some(mut message) = cached {
+                    message.labels = change.labels_after.clone();
+                    cache.put(account, &message);
+                }
+                entry.changes.push(change);
+                report.modified.push(id.clone());
+            }
+            Err(()) => report.failed.push(id.clone()),
+        }
+    }
+    for id in &items.thread_ids {
+        // Note the labels of every message beforehand so the change can
+        // be undone message by message
+        let messages = match threads_get(token, id).await {
+            Ok(thread) => thread.messages,
+            Err(()) => {
+                report.failed.push(id.clone());
+                continue;
+            }
+        };
+        match threads_modify(token, id, add, remove).await {
+            Ok(()) => {
+                for msg in messages {
+                    let change = MessageChange::from_labels(&msg.id, &msg.labelIds, add, remove);
+                    if let Some(mut message) = cache.get(account, &msg.id) {
+                        message.labels = change.labels_after.clone();
+                        cache.put(account, &message);
+                    }
+                    entry.changes.push(change);
+                }
+                report.modified.push(id.clone());
+            }
+            Err(()) => report.failed.push(id.clone()),
+        }
+    }
+    if !entry.is_empty() {
+        cache.add_journal_entry(account, &entry);
+    }
+    report
+}
+
+/// Archives the selected messages and threads by removing them from the
+/// Inbox. Needs the gmail.modify scope.
+#[post("/api/archive", data = "<request>")]
+pub async fn archive(
+    request: Json<SelectedItems>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<ModifyReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !can_modify(cookies) {
+        return Err(BadRequest(
+            "Archiving needs permission to modify Gmail, sign in again to grant it".to_string(),
+        ));
+    }
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    Ok(Json(
+        modify_selected(
+            token.value(),
+            &account,
+            cache,
+            &request,
+            &[],
+            &["INBOX"],
+            "archive",
+        )
+        .await,
+    ))
+}
+
+/// Returns the ID of the user label called `name`, creating the label if
+/// it does not exist yet.
+async fn find_or_create_label(token: &str, name: &str) -> Result<String, BadRequest<String>> {
+    let labels = labels_list(token)
+        .await
+        .map_err(|_| BadRequest("Could not list the labels".to_string()))?;
+    match labels
+        .into_iter()
+        .find(|label| label.name.eq_ignore_ascii_case(name))
+    {
+        Some(label) => Ok(label.id),
+        None => labels_create(token, name)
+            .await
+            .map(|label| label.id)
+            .map_err(|_| BadRequest(format!("Could not create the label {}", name))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LabelRequest {
+    #[serde(flatten)]
+    items: SelectedItems,
+    /// Name of the label to apply, created if it does not exist yet.
+    label: String,
+}
+
+/// Applies a user label such as "To review" to the selected messages and
+/// threads. Needs the gmail.modify scope.
+#[post("/api/label", data = "<request>")]
+pub async fn apply_label(
+    request: Json<LabelRequest>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<ModifyReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !can_modify(cookies) {
+        return Err(BadRequest(
+            "Labelling needs permission to modify Gmail, sign in again to grant it".to_string(),
+        ));
+    }
+    let name = request.label.trim();
+    if name.is_empty() {
+        return Err(BadRequest("Choose a label".to_string()));
+    }
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    let label_id = find_or_create_label(token.value(), name).await?;
+    Ok(Json(
+        modify_selected(
+            token.value(),
+            &account,
+            cache,
+            &request.items,
+            &[label_id.as_str()],
+            &[],
+            "label",
+        )
+        .await,
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct FilterRequest {
+    /// Sender address or domain to match.
+    sender: Option<String>,
+    /// Mailing list ID to match, see `/api/newsletters`.
+    list_id: Option<String>,
+    /// Gmail search query to match.
+    query: Option<String>,
+    /// One of "archive", "label" or "delete".
+    action: String,
+    /// Label to apply for the "label" action.
+    label: Option<String>,
+    /// Only show the filter which would be created.
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+pub struct FilterPreview {
+    dry_run: bool,
+    criteria: FilterCriteria,
+    /// The criteria as a Gmail search, to check what the filter matches.
+    search: String,
+    description: String,
+    /// Gmail's estimate of how many existing messages match.
+    matching_estimate: Option<i32>,
+    filter_id: Option<String>,
+}
+
+/// Turns a sender, list ID or query into filter criteria along with the
+/// equivalent Gmail search.
+fn filter_criteria(request: &FilterRequest) -> Result<(FilterCriteria, String), String> {
+    let non_empty = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let mut criteria = FilterCriteria::default();
+    let mut terms = vec![];
+    if let Some(sender) = non_empty(&request.sender) {
+        terms.push(format!("from:({})", sender));
+        criteria.from = Some(sender);
+    }
+    let mut query_terms = vec![];
+    if let Some(list_id) = non_empty(&request.list_id) {
+        query_terms.push(format!("list:({})", list_id));
+    }
+    if let Some(query) = non_empty(&request.query) {
+        query_terms.push(query);
+    }
+    if !query_terms.is_empty() {
+        let query = query_terms.join(" ");
+        terms.push(query.clone());
+        criteria.query = Some(query);
+    }
+    if terms.is_empty() {
+        return Err("Choose a sender, list or query to filter on".to_string());
+    }
+    Ok((criteria, terms.join(" ")))
+}
+
+/// Creates a Gmail filter which archives, labels or deletes future mail
+/// from a sender, mailing list or search. With `dry_run` set the filter is
+/// only previewed. Needs the gmail.settings.basic scope.
+#[post("/api/filters", data = "<request>")]
+pub async fn create_filter(
+    request: Json<FilterRequest>,
+    cookies: &CookieJar<'_>,
+) -> Result<Json<FilterPreview>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !request.dry_run && (!has_scope(cookies, SETTINGS_SCOPE) || !can_modify(cookies)) {
+        return Err(BadRequest(
+            "Creating filters needs permission to change Gmail settings, use Allow filters to sign in again and grant it"
+                .to_string(),
+        ));
+    }
+    let (criteria, search) = filter_criteria(&request).map_err(BadRequest)?;
+    let label_name = request.label.as_deref().map(str::trim).unwrap_or_default();
+    let description = match request.action.as_str() {
+        "archive" => "Skip the Inbox (archive it)".to_string(),
+        "delete" => "Delete it".to_string(),
+        "label" if !label_name.is_empty() => format!("Apply the label {}", label_name),
+        "label" => return Err(BadRequest("Choose a label".to_string())),
+        action => return Err(BadRequest(format!("Unknown filter action {}", action))),
+    };
+    let matching_estimate = messages_list(token.value(), 1, None, Some(&search))
+        .await
+        .ok()
+        .and_then(|list| list.resultSizeEstimate);
+
+    let mut preview = FilterPreview {
+        dry_run: request.dry_run,
+        criteria,
+        search,
+        description,
+        matching_estimate,
+        filter_id: None,
+    };
+    if request.dry_run {
+        return Ok(Json(preview));
+    }
+
+    let action = match request.action.as_str() {
+        "archive" => FilterAction {
+            removeLabelIds: vec!["INBOX".to_string()],
+            ..Default::default()
+        },
+        "delete" => FilterAction {
+            addLabelIds: vec!["TRASH".to_string()],
+            ..Default::default()
+        },
+        _ => FilterAction {
+            addLabelIds: vec![find_or_create_label(token.value(), label_name).await?],
+            ..Default::default()
+        },
+    };
+    let filter = Filter {
+        id: None,
+        criteria: preview.criteria.clone(),
+        action,
+    };
+    let created = filters_create(token.value(), &filter)
+        .await
+        .map_err(|_| BadRequest("Gmail did not accept the filter".to_string()))?;
+    preview.filter_id = created.id;
+    Ok(Json(preview))
+}
+
+/// Messages to act on in bulk. Each criterion given narrows the selection
+/// further; at least one is needed.
+#[derive(Deserialize)]
+pub struct BulkSelection {
+    /// Gmail search query, e.g. `older_than:2y larger:5M`.
+    query: Option<String>,
+    /// Size bucket name.
+    bucket: Option<String>,
+    /// Sender address, or domain if `group` is "domain".
+    sender: Option<String>,
+    group: Option<String>,
+    /// Rule category.
+    category: Option<String>,
+}
+
+/// Resolves a bulk selection to messages, largest first. Messages matching
+/// a query which have not been scanned yet are fetched and cached.
+async fn select_messages(
+    token: &str,
+    account: &str,
+    cache: &MessageCache,
+    settings: &SettingsStore,
+    selection: &BulkSelection,
+) -> Result<Vec<CachedMessage>, String> {
+    if selection.query.is_none()
+        && selection.bucket.is_none()
+        && selection.sender.is_none()
+        && selection.category.is_none()
+    {
+        return Err("Choose a query, bucket, sender or category".to_string());
+    }
+
+    let mut messages = match &selection.query {
+        Some(query) => {
+            let mut messages = Vec::new();
+            let mut page_token: Option<String> = None;
+            loop {
+                let list = messages_list(token, 500, page_token.as_deref(), Some(query))
+                    .await
+                    .map_err(|_| "Could not search the mailbox".to_string())?;
+                for entry in list.messages {
+                    if let Some(cached) = cache.get(account, &entry.id) {
+                        messages.push(cached);
+                    } else if let Ok(msg) = message_get(token, &entry.id).await {
+                        let cached = CachedMessage::from_message(msg);
+                        cache.put(account, &cached);
+                        messages.push(cached);
+                    }
+                }
+                match list.nextPageToken {
+                    Some(next_token) => page_token = Some(next_token),
+                    None => break,
+                }
+            }
+            messages
+        }
+        None => cache.all(account),
+    };
+
+    if let Some(bucket) = &selection.bucket {
+        let classifier = settings.size_classifier();
+        messages.retain(|m| classifier.classify(m.size_estimate as i64) == bucket);
+    }
+    if let Some(sender) = &selection.sender {
+        let by_domain = selection.group.as_deref() == Some("domain");
+        messages.retain(|m| sender_key(&m.sender, by_domain) == *sender);
+    }
+    if let Some(category) = &selection.category {
+        let names = label_names(token).await;
+        let engine = rule_engine(settings);
+        messages.retain(|m| engine.categorize(m, &names) == Some(category.as_str()));
+    }
+    messages.sort_by_key(|message| Reverse(message.size_estimate));
+    Ok(messages)
+}
+
+#[derive(Deserialize)]
+pub struct BulkTrashRequest {
+    #[serde(flatten)]
+    selection: BulkSelection,
+    /// Only report what would be trashed.
+    dry_run: bool,
+    /// IDs of the messages the dry run listed, needed to trash them.
+    #[serde(default)]
+    message_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BulkTrashReport {
+    dry_run: bool,
+    message_count: usize,
+    total_size: i64,
+    messages: Vec<SearchResult>,
+    trashed: usize,
+    failed: usize,
+}
+
+/// Moves every message matching a selection to the Trash with
+/// batchModify calls. With `dry_run` set nothing is changed and the
+/// report lists the messages which would be trashed. Otherwise
+/// `message_ids` must be the messages of that report; if the selection
+/// no longer resolves to exactly those, nothing is trashed.
+#[post("/api/trash/bulk", data = "<request>")]
+pub async fn bulk_trash(
+    request: Json<BulkTrashRequest>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Result<Json<BulkTrashReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !request.dry_run && !can_modify(cookies) {
+        return Err(BadRequest(
+            "Moving mail to the Trash needs permission to modify Gmail, sign in again to grant it"
+                .to_string(),
+        ));
+    }
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    if !request.dry_run && request.message_ids.is_empty() {
+        return Err(BadRequest(
+            "Preview the selection before moving it to the Trash".to_string(),
+        ));
+    }
+    let messages = select_messages(token.value(), &account, cache, settings, &request.selection)
+        .await
+        .map_err(BadRequest)?;
+    if !request.dry_run {
+        let previewed: HashSet<&str> = request.message_ids.iter().map(String::as_str).collect();
+        let selected: HashSet<&str> = messages.iter().map(|m| m.id.as_str()).collect();
+        if previewed != selected {
+            return Err(BadRequest(format!(
+                "The selection changed since the preview ({} new, {} gone), preview it again",
+                selected.difference(&previewed).count(),
+                previewed.difference(&selected).count()
+            )));
+        }
+    }
+
+    let classifier = settings.size_classifier();
+    let mut report = BulkTrashReport {
+        dry_run: request.dry_run,
+        message_count: messages.len(),
+        total_size: messages.iter().map(|m| m.size_estimate as i64).sum(),
+        messages: messages
+            .iter()
+            .map(|m| SearchResult::from_cached(m, &classifier))
+            .collect(),
+        trashed: 0,
+        failed: 0,
+    };
+    if request.dry_run {
+        return Ok(Json(report));
+    }
+
+    let mut entry = JournalEntry::new("bulk_trash");
+    for chunk in messages.chunks(BATCH_MODIFY_LIMIT) {
+        let ids: Vec<String> = chunk.iter().map(|m| m.id.clone()).collect();
+        match messages_batch_modify(token.value(), &ids, &["TRASH"], &[]).await {
+            Ok(()) => {
+                for message in chunk {
+                    entry
+                        .changes
+                        .push(MessageChange::new(message, &["TRASH"], &[]));
+                    cache.remove(&account, &message.id);
+                }
+                report.trashed += chunk.len();
+            }
+            Err(()) => report.failed += chunk.len(),
+        }
+    }
+    if !entry.is_empty() {
+        cache.add_journal_entry(&account, &entry);
+    }
+    Ok(Json(report))
+}
+
+#[derive(Deserialize)]
+pub struct ExportMessagesRequest {
+    /// Messages to export; the selection is used if there are none.
+    #[serde(default)]
+    message_ids: Vec<String>,
+    #[serde(flatten)]
+    selection: BulkSelection,
+    /// "mbox" or "eml".
+    format: String,
+    /// Local folder to write to, the Downloads folder if not given.
+    folder: Option<String>,
+}
+
+/// Writes full copies of the selected messages to a local mbox file or
+/// `.eml` files with an index, so that old mail can be kept on disk
+/// before it is cleaned up.
+#[post("/api/export/messages", data = "<request>")]
+pub async fn export_messages(
+    request: Json<ExportMessagesRequest>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Result<Json<ArchiveReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    let format = ArchiveFormat::parse(&request.format)
+        .ok_or_else(|| BadRequest(format!("Unknown export format {}", request.format)))?;
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    // Requested messages which have not been scanned are fetched; the ones
+    // which cannot be are reported as failed rather than left out
+    let mut messages = Vec::new();
+    let mut missing = Vec::new();
+    if request.message_ids.is_empty() {
+        messages = select_messages(token.value(), &account, cache, settings, &request.selection)
+            .await
+            .map_err(BadRequest)?;
+    } else {
+        for id in &request.message_ids {
+            if let Some(cached) = cache.get(&account, id) {
+                messages.push(cached);
+            } else if let Ok(msg) = message_get(token.value(), id).await {
+                let cached = CachedMessage::from_message(msg);
+                cache.put(&account, &cached);
+                messages.push(cached);
+            } else {
+                missing.push(id.clone());
+            }
+        }
+    }
+    if messages.is_empty() && missing.is_empty() {
+        return Err(BadRequest("No messages to export".to_string()));
+    }
+    let folder =
+        local_folder(request.folder.as_deref(), "Gmail Cleaner Archive").map_err(BadRequest)?;
+    let mut report = archive_messages(token.value(), &messages, &folder, format).await;
+    report.failed.extend(missing);
+    Ok(Json(report))
+}
+
+/// Lists the most recent modifying actions, newest first.
+#[get("/api/journal?<limit>")]
+pub async fn journal(
+    limit: Option<usize>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Json<Vec<JournalEntry>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let mut entries = cache.journal(&account);
+                entries.truncate(limit.unwrap_or(20));
+                Json(entries)
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+#[derive(Serialize)]
+pub struct UndoReport {
+    entry_id: i64,
+    restored: usize,
+    failed: usize,
+}
+
+/// Reverts a journalled action, by default the latest one not undone yet.
+/// Trashed messages are untrashed, messages the action added are trashed
+/// and label changes reversed. Gmail empties the Trash after 30 days, so
+/// older trash actions cannot be undone.
+#[post("/api/undo?<id>")]
+pub async fn undo(
+    id: Option<i64>,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+) -> Result<Json<UndoReport>, BadRequest<String>> {
+    let token = cookies
+        .get_private("token")
+        .ok_or_else(|| BadRequest("Not signed in".to_string()))?;
+    if !can_modify(cookies) {
+        return Err(BadRequest(
+            "Undoing needs permission to modify Gmail, sign in again to grant it".to_string(),
+        ));
+    }
+    let account = account_email(token.value(), cookies)
+        .await
+        .ok_or_else(|| BadRequest("Could not look up the account".to_string()))?;
+    let entry = cache
+        .journal(&account)
+        .into_iter()
+        .find(|entry| match id {
+            Some(id) => entry.id == id,
+            None => !entry.undone,
+        })
+        .ok_or_else(|| BadRequest("There is nothing to undo".to_string()))?;
+    if entry.undone {
+        return Err(BadRequest(
+            "This action has already been undone".to_string(),
+        ));
+    }
+    let trashed: Vec<&MessageChange> = entry.changes.iter().filter(|c| c.trashed()).collect();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    if (!trashed.is_empty() || !entry.thread_ids.is_empty()) && !entry.within_trash_window(now) {
+        return Err(BadRequest(
+            "Gmail has emptied these messages from the Trash after 30 days".to_string(),
+        ));
+    }
+
+    let mut report = UndoReport {
+        entry_id: entry.id,
+        restored: 0,
+        failed: 0,
+    };
+    for change in trashed {
+        match messages_untrash(token.value(), &change.id).await {
+            Ok(()) => {
+                if let Ok(msg) = message_get(token.value(), &change.id).await {
+                    cache.put(&account, &CachedMessage::from_message(msg));
+                }
+                report.restored += 1;
+            }
+            Err(()) => report.failed += 1,
+        }
+    }
+    // The messages of restored threads come back into the cache with the
+    // next sync
+    for thread_id in &entry.thread_ids {
+        match threads_untrash(token.value(), thread_id).await {
+            Ok(()) => report.restored += 1,
+            Err(()) => report.failed += 1,
+        }
+    }
+    for id in &entry.created_ids {
+        match messages_trash(token.value(), id).await {
+            Ok(()) => {
+                cache.remove(&account, id);
+                report.restored += 1;
+            }
+            Err(()) => report.failed += 1,
+        }
+    }
+    for ((add, remove), ids) in entry.reverse_label_changes() {
+        let add: Vec<&str> = add.iter().map(|l| l.as_str()).collect();
+        let remove: Vec<&str> = remove.iter().map(|l| l.as_str()).collect();
+        for chunk in ids.chunks(BATCH_MODIFY_LIMIT) {
+            match messages_batch_modify(token.value(), chunk, &add, &remove).await {
+                Ok(()) => {
+                    for id in chunk {
+                        if let Ok(msg) = message_get(token.value(), id).await {
+                            cache.put(&account, &CachedMessage::from_message(msg));
+                        }
+                    }
+                    report.restored += chunk.len();
+                }
+                Err(()) => report.failed += chunk.len(),
+            }
+        }
+    }
+    // Leave partly failed actions in the journal so they can be retried
+    if report.failed == 0 {
+        cache.set_journal_undone(&account, entry.id);
+    }
+    Ok(Json(report))
+}
+
+/// Returns the size buckets messages are classified into.
+#[get("/api/settings/buckets")]
+pub fn buckets(settings: &State<SettingsStore>) -> Json<SizeClassifier> {
+    Json(settings.size_classifier())
+}
+
+/// Replaces the size buckets messages are classified into.
+#[post("/api/settings/buckets", data = "<classifier>")]
+pub fn update_buckets(
+    classifier: Json<SizeClassifier>,
+    settings: &State<SettingsStore>,
+) -> Result<Json<SizeClassifier>, BadRequest<String>> {
+    let classifier = classifier.into_inner().validate().map_err(BadRequest)?;
+    settings.update(|s| s.size_classifier = classifier.clone());
+    Ok(Json(classifier))
+}
+
+/// Returns the user-defined classification rules.
+#[get("/api/rules")]
+pub fn rules(settings: &State<SettingsStore>) -> Json<Vec<Rule>> {
+    Json(settings.rules())
+}
+
+/// Replaces the classification rules. Rules are applied in order and a
+/// message gets the category of the first one it matches.
+#[post("/api/rules", data = "<rules>")]
+pub fn update_rules(
+    rules: Json<Vec<Rule>>,
+    settings: &State<SettingsStore>,
+) -> Result<Json<Vec<Rule>>, BadRequest<String>> {
+    let rules = rules.into_inner();
+    validate_rules(&rules).map_err(BadRequest)?;
+    settings.update(|s| s.rules = rules.clone());
+    Ok(Json(rules))
+}
+
+/// Totals the storage of the scanned messages per rule category.
+#[get("/api/rules/categories")]
+pub async fn rule_categories(
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Json<Vec<CategoryTotal>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let names = label_names(token.value()).await;
+                Json(categories_by_size(
+                    &cache.all(&account),
+                    &rule_engine(settings),
+                    &names,
+                ))
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
+/// Lists the scanned messages the rules put in `category`, largest first.
+#[get("/api/rules/messages?<category>")]
+pub async fn rule_messages(
+    category: String,
+    cookies: &CookieJar<'_>,
+    cache: &State<MessageCache>,
+    settings: &State<SettingsStore>,
+) -> Json<Vec<SearchResult>> {
+    match cookies.get_private("token") {
+        Some(token) => match account_email(token.value(), cookies).await {
+            Some(account) => {
+                let names = label_names(token.value()).await;
+                let engine = rule_engine(settings);
+                let mut messages: Vec<CachedMessage> = cache
+                    .all(&account)
+                    .into_iter()
+                    .filter(|m| engine.categorize(m, &names) == Some(category.as_str()))
+                    .collect();
+                messages.sort_by_key(|message| Reverse(message.size_estimate));
+                let classifier = settings.size_classifier();
+                Json(
+                    messages
+                        .iter()
+                        .map(|m| SearchResult::from_cached(m, &classifier))
+                        .collect(),
+                )
+            }
+            None => Json(vec![]),
+        },
+        None => Json(vec![]),
+    }
+}
+
 #[get("/api/profile")]
 pub async fn profile(cookies: &CookieJar<'_>) -> Json<User> {
     match cookies.get_private("token") {
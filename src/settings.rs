@@ -0,0 +1,109 @@
+use crate::classifier::SizeClassifier;
+use crate::rules::Rule;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Returns the directory the app keeps its local data in, creating it if
+/// needed.
+pub fn app_data_dir() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("gmail-cleaner");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Resolves the local folder the user chose to save files in, creating it
+/// if needed. An empty choice means `default_name` in the Downloads
+/// folder; a leading `~` stands for the home directory.
+pub fn local_folder(folder: Option<&str>, default_name: &str) -> Result<PathBuf, String> {
+    let folder = folder.map(str::trim).unwrap_or_default();
+    let path = if folder.is_empty() {
+        dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| "Could not find the Downloads folder".to_string())?
+            .join(default_name)
+    } else if let Some(rest) = folder.strip_prefix('~') {
+        let home = dirs::home_dir().ok_or_else(|| "Could not find the home folder".to_string())?;
+        home.join(rest.trim_start_matches(['/', '\\']))
+    } else {
+        PathBuf::from(folder)
+    };
+    if !path.is_absolute() {
+        return Err(format!("{} is not a full path", folder));
+    }
+    std::fs::create_dir_all(&path)
+        .map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Settings {
+    #[serde(default)]
+    pub size_classifier: SizeClassifier,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// User settings persisted as JSON in the app data directory.
+pub struct SettingsStore {
+    settings: Mutex<Settings>,
+    path: Option<PathBuf>,
+}
+
+impl SettingsStore {
+    /// Loads the settings file, using the defaults if it does not exist or
+    /// cannot be read. Size buckets which do not pass validation, e.g.
+    /// after the file was edited by hand, are replaced by the defaults.
+    pub fn load() -> Self {
+        let path = app_data_dir().map(|dir| dir.join("settings.json"));
+        let mut settings: Settings = path
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|data| match serde_json::from_str(&data) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    println!("settings error: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        match settings.size_classifier.clone().validate() {
+            Ok(classifier) => settings.size_classifier = classifier,
+            Err(e) => {
+                println!("settings error: {}, using the default size buckets", e);
+                settings.size_classifier = SizeClassifier::default();
+            }
+        }
+        Self {
+            settings: Mutex::new(settings),
+            path,
+        }
+    }
+
+    pub fn size_classifier(&self) -> SizeClassifier {
+        self.settings.lock().unwrap().size_classifier.clone()
+    }
+
+    pub fn rules(&self) -> Vec<Rule> {
+        self.settings.lock().unwrap().rules.clone()
+    }
+
+    /// Applies `change` to the settings and writes them to disk.
+    pub fn update<F>(&self, change: F)
+    where
+        F: FnOnce(&mut Settings),
+    {
+        let mut settings = self.settings.lock().unwrap();
+        change(&mut settings);
+        if let Some(path) = &self.path {
+            match serde_json::to_string_pretty(&*settings) {
+                Ok(data) => {
+                    if let Err(e) = std::fs::write(path, data) {
+                        println!("settings error: {}", e);
+                    }
+                }
+                Err(e) => println!("settings error: {}", e),
+            }
+        }
+    }
+}
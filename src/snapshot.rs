@@ -0,0 +1,87 @@
+use crate::cache::CachedMessage;
+use crate::classifier::SizeClassifier;
+use crate::stats::{sender_address, total_size_by, total_size_by_label};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Storage totals of a mailbox at the time a scan completed.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    /// Row ID in the cache database, 0 until the snapshot is stored.
+    #[serde(default)]
+    pub id: i64,
+    /// Milliseconds since the epoch.
+    pub taken_at: i64,
+    /// Total number of messages in the mailbox, from `Profile.messagesTotal`.
+    pub messages_total: i32,
+    pub scanned_count: usize,
+    pub total_size: i64,
+    pub buckets: BTreeMap<String, i64>,
+    pub labels: BTreeMap<String, i64>,
+    pub senders: BTreeMap<String, i64>,
+}
+
+impl Snapshot {
+    pub fn take(
+        messages: &[CachedMessage],
+        messages_total: i32,
+        classifier: &SizeClassifier,
+    ) -> Self {
+        let taken_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        Self {
+            id: 0,
+            taken_at,
+            messages_total,
+            scanned_count: messages.len(),
+            total_size: messages.iter().map(|m| m.size_estimate as i64).sum(),
+            buckets: total_size_by(messages, |m| {
+                classifier.classify(m.size_estimate as i64).to_string()
+            }),
+            labels: total_size_by_label(messages),
+            senders: total_size_by(messages, |m| sender_address(&m.sender)),
+        }
+    }
+}
+
+/// Change in storage between two snapshots; every value is `to - from`.
+#[derive(Serialize)]
+pub struct SnapshotDiff {
+    pub from: i64,
+    pub to: i64,
+    pub messages_total: i32,
+    pub total_size: i64,
+    pub buckets: BTreeMap<String, i64>,
+    pub labels: BTreeMap<String, i64>,
+    pub senders: BTreeMap<String, i64>,
+}
+
+impl SnapshotDiff {
+    pub fn between(from: &Snapshot, to: &Snapshot) -> Self {
+        Self {
+            from: from.id,
+            to: to.id,
+            messages_total: to.messages_total - from.messages_total,
+            total_size: to.total_size - from.total_size,
+            buckets: diff_totals(&from.buckets, &to.buckets),
+            labels: diff_totals(&from.labels, &to.labels),
+            senders: diff_totals(&from.senders, &to.senders),
+        }
+    }
+}
+
+/// Subtracts each total in `from` from the one in `to`, leaving out the
+/// keys which did not change.
+fn diff_totals(from: &BTreeMap<String, i64>, to: &BTreeMap<String, i64>) -> BTreeMap<String, i64> {
+    let mut diff = BTreeMap::new();
+    for key in from.keys().chain(to.keys()) {
+        let delta = to.get(key).unwrap_or(&0) - from.get(key).unwrap_or(&0);
+        if delta != 0 {
+            diff.insert(key.clone(), delta);
+        }
+    }
+    diff
+}
@@ -0,0 +1,266 @@
+use crate::cache::CachedMessage;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+/// Adds up message sizes under the key returned by `key` for each message.
+pub fn total_size_by<F>(messages: &[CachedMessage], key: F) -> BTreeMap<String, i64>
+where
+    F: Fn(&CachedMessage) -> String,
+{
+    let mut totals = BTreeMap::new();
+    for message in messages {
+        *totals.entry(key(message)).or_insert(0) += message.size_estimate as i64;
+    }
+    totals
+}
+
+/// Adds up message sizes under every label of each message.
+pub fn total_size_by_label(messages: &[CachedMessage]) -> BTreeMap<String, i64> {
+    let mut totals = BTreeMap::new();
+    for message in messages {
+        for label in &message.labels {
+            *totals.entry(label.clone()).or_insert(0) += message.size_estimate as i64;
+        }
+    }
+    totals
+}
+
+/// Storage used by all the messages of a thread.
+#[derive(Serialize)]
+pub struct ThreadTotal {
+    pub thread_id: String,
+    /// Subject of the earliest message in the thread.
+    pub subject: String,
+    pub message_count: usize,
+    pub total_size: i64,
+    /// Date of the latest message, in milliseconds since the epoch.
+    pub latest_date: i64,
+}
+
+/// Groups messages by thread and returns the threads, largest first.
+pub fn threads_by_size(messages: &[CachedMessage]) -> Vec<ThreadTotal> {
+    let mut threads: HashMap<&str, Vec<&CachedMessage>> = HashMap::new();
+    for message in messages {
+        threads.entry(&message.thread_id).or_default().push(message);
+    }
+    let mut totals: Vec<ThreadTotal> = threads
+        .into_values()
+        .map(|mut thread| {
+            thread.sort_by_key(|m| m.date);
+            ThreadTotal {
+                thread_id: thread[0].thread_id.clone(),
+                subject: thread[0].subject.clone(),
+                message_count: thread.len(),
+                total_size: thread.iter().map(|m| m.size_estimate as i64).sum(),
+                latest_date: thread[thread.len() - 1].date,
+            }
+        })
+        .collect();
+    totals.sort_by_key(|total| Reverse(total.total_size));
+    totals
+}
+
+/// Extracts the lowercased email address from a `From` header value such
+/// as `"Jane Doe" <Jane@Example.com>`.
+pub fn sender_address(from: &str) -> String {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    address.trim().trim_matches('"').to_lowercase()
+}
+
+/// Returns the domain of a normalized sender address.
+pub fn sender_domain(address: &str) -> String {
+    match address.rsplit_once('@') {
+        Some((_, domain)) => domain.to_string(),
+        None => address.to_string(),
+    }
+}
+
+/// Storage used by the messages of one sender address or domain.
+#[derive(Serialize)]
+pub struct SenderTotal {
+    pub sender: String,
+    pub message_count: usize,
+    pub total_size: i64,
+}
+
+/// Groups messages by sender address, or by sender domain if `by_domain`
+/// is set, and returns the senders using the most storage first.
+pub fn senders_by_size(messages: &[CachedMessage], by_domain: bool) -> Vec<SenderTotal> {
+    let mut senders: HashMap<String, SenderTotal> = HashMap::new();
+    for message in messages {
+        let key = sender_key(&message.sender, by_domain);
+        let total = senders.entry(key.clone()).or_insert(SenderTotal {
+            sender: key,
+            message_count: 0,
+            total_size: 0,
+        });
+        total.message_count += 1;
+        total.total_size += message.size_estimate as i64;
+    }
+    let mut totals: Vec<SenderTotal> = senders.into_values().collect();
+    totals.sort_by_key(|total| Reverse(total.total_size));
+    totals
+}
+
+/// Returns the address or domain a `From` header value is grouped under.
+pub fn sender_key(from: &str, by_domain: bool) -> String {
+    let address = sender_address(from);
+    if by_domain {
+        sender_domain(&address)
+    } else {
+        address
+    }
+}
+
+/// Storage used by the messages carrying one label.
+#[derive(Serialize)]
+pub struct LabelTotal {
+    pub label_id: String,
+    pub name: String,
+    pub message_count: usize,
+    pub total_size: i64,
+}
+
+/// Returns a readable name for a label, turning system label IDs such as
+/// `CATEGORY_PROMOTIONS` into `Promotions`.
+pub fn label_display_name(id: &str, names: &HashMap<String, String>) -> String {
+    match id {
+        "INBOX" => "Inbox".to_string(),
+        "SENT" => "Sent".to_string(),
+        "DRAFT" => "Drafts".to_string(),
+        "SPAM" => "Spam".to_string(),
+        "TRASH" => "Trash".to_string(),
+        "STARRED" => "Starred".to_string(),
+        "IMPORTANT" => "Important".to_string(),
+        "UNREAD" => "Unread".to_string(),
+        "CHAT" => "Chat".to_string(),
+        "CATEGORY_PERSONAL" => "Primary".to_string(),
+        "CATEGORY_PROMOTIONS" => "Promotions".to_string(),
+        "CATEGORY_SOCIAL" => "Social".to_string(),
+        "CATEGORY_UPDATES" => "Updates".to_string(),
+        "CATEGORY_FORUMS" => "Forums".to_string(),
+        _ => names.get(id).cloned().unwrap_or_else(|| id.to_string()),
+    }
+}
+
+/// Groups messages under each of their labels and returns the labels using
+/// the most storage first. `names` maps label IDs to their names.
+pub fn labels_by_size(
+    messages: &[CachedMessage],
+    names: &HashMap<String, String>,
+) -> Vec<LabelTotal> {
+    let mut labels: HashMap<&str, LabelTotal> = HashMap::new();
+    for message in messages {
+        for label in &message.labels {
+            let total = labels.entry(label).or_insert(LabelTotal {
+                label_id: label.clone(),
+                name: label_display_name(label, names),
+                message_count: 0,
+                total_size: 0,
+            });
+            total.message_count += 1;
+            total.total_size += message.size_estimate as i64;
+        }
+    }
+    let mut totals: Vec<LabelTotal> = labels.into_values().collect();
+    totals.sort_by_key(|total| Reverse(total.total_size));
+    totals
+}
+
+/// Converts milliseconds since the epoch to a UTC (year, month) pair.
+fn year_month(millis: i64) -> (i64, u32) {
+    let (year, month, _) = civil_date(millis);
+    (year, month)
+}
+
+/// Converts milliseconds since the epoch to a UTC (year, month, day).
+pub fn civil_date(millis: i64) -> (i64, u32, u32) {
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = millis.div_euclid(86_400_000) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month as u32, day as u32)
+}
+
+/// Formats milliseconds since the epoch as an ISO 8601 UTC date and time.
+pub fn iso_date(millis: i64) -> String {
+    let (year, month, day) = civil_date(millis);
+    let seconds = millis.rem_euclid(86_400_000) / 1000;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Formats a size in bytes for people, e.g. "1.5 MB".
+pub fn format_size(size: i64) -> String {
+    if size >= 1_073_741_824 {
+        format!("{:.1} GB", size as f64 / 1_073_741_824.0)
+    } else if size >= 1_048_576 {
+        format!("{:.1} MB", size as f64 / 1_048_576.0)
+    } else {
+        format!("{:.1} KB", size as f64 / 1024.0)
+    }
+}
+
+/// Storage used by the messages received in one month or year.
+#[derive(Serialize)]
+pub struct AgeTotal {
+    /// `YYYY-MM` when grouping by month, `YYYY` when grouping by year.
+    pub period: String,
+    pub message_count: usize,
+    pub total_size: i64,
+    /// Fraction of the total size of all the messages.
+    pub share: f64,
+}
+
+/// Groups messages by the month, or the year if `by_year` is set, of
+/// their internal date and returns the periods in chronological order.
+pub fn size_by_age(messages: &[CachedMessage], by_year: bool) -> Vec<AgeTotal> {
+    let mut periods: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+    for message in messages {
+        let (year, month) = year_month(message.date);
+        let period = if by_year {
+            format!("{:04}", year)
+        } else {
+            format!("{:04}-{:02}", year, month)
+        };
+        let total = periods.entry(period).or_insert((0, 0));
+        total.0 += 1;
+        total.1 += message.size_estimate as i64;
+    }
+    let total_size: i64 = periods.values().map(|(_, size)| size).sum();
+    periods
+        .into_iter()
+        .map(|(period, (message_count, size))| AgeTotal {
+            period,
+            message_count,
+            total_size: size,
+            share: if total_size > 0 {
+                size as f64 / total_size as f64
+            } else {
+                0.0
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+use crate::cache::{CachedMessage, MessageCache};
+use crate::gmail_client::{
+    history_list, message_get, messages_list, profile_get, HistoryError, HistoryLabelChange,
+};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Page size used when listing every message of the mailbox.
+const FULL_SCAN_PAGE_SIZE: u32 = 500;
+
+#[derive(Serialize, Default)]
+pub struct SyncReport {
+    /// Either "incremental" or "full".
+    pub mode: String,
+    pub added: usize,
+    pub deleted: usize,
+    pub relabeled: usize,
+    pub history_id: String,
+}
+
+/// Brings the local metadata cache of an account up to date. Changes made
+/// since the last stored history ID are applied from the history API; if
+/// there is no stored history ID, or Gmail no longer has history that old,
+/// the whole mailbox is scanned instead.
+pub async fn sync(token: &str, account: &str, cache: &MessageCache) -> Result<SyncReport, ()> {
+    if let Some(start_history_id) = cache.history_id(account) {
+        match incremental_sync(token, account, cache, &start_history_id).await {
+            Ok(report) => return Ok(report),
+            Err(HistoryError::Expired) => {
+                println!("history {} expired, running a full scan", start_history_id);
+            }
+            Err(HistoryError::Failed) => return Err(()),
+        }
+    }
+    full_sync(token, account, cache).await
+}
+
+async fn incremental_sync(
+    token: &str,
+    account: &str,
+    cache: &MessageCache,
+    start_history_id: &str,
+) -> Result<SyncReport, HistoryError> {
+    let mut report = SyncReport {
+        mode: "incremental".to_string(),
+        ..Default::default()
+    };
+    let mut page_token: Option<String> = None;
+    loop {
+        let list = history_list(token, start_history_id, page_token.as_deref()).await?;
+        for history in list.history {
+            for change in history.messagesAdded {
+                if let Ok(msg) = message_get(token, &change.message.id).await {
+                    cache.put(account, &CachedMessage::from_message(msg));
+                    report.added += 1;
+                }
+            }
+            for change in history.messagesDeleted {
+                cache.remove(account, &change.message.id);
+                report.deleted += 1;
+            }
+            for change in history.labelsAdded {
+                if relabel(account, cache, &change, true) {
+                    report.relabeled += 1;
+                }
+            }
+            for change in history.labelsRemoved {
+                if relabel(account, cache, &change, false) {
+                    report.relabeled += 1;
+                }
+            }
+        }
+        report.history_id = list.historyId;
+        match list.nextPageToken {
+            Some(next_token) => page_token = Some(next_token),
+            None => break,
+        }
+    }
+    cache.set_history_id(account, &report.history_id);
+    Ok(report)
+}
+
+/// Applies a label change to a cached message. Returns false if the
+/// message is not cached.
+fn relabel(account: &str, cache: &MessageCache, change: &HistoryLabelChange, added: bool) -> bool {
+    match cache.get(account, &change.message.id) {
+        Some(mut cached) => {
+            cached
+                .labels
+                .retain(|label| !change.labelIds.contains(label));
+            if added {
+                cached.labels.extend(change.labelIds.iter().cloned());
+            }
+            cache.put(account, &cached);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Lists every message in the mailbox, fetches the ones missing from the
+/// cache and drops cached messages which no longer exist.
+async fn full_sync(token: &str, account: &str, cache: &MessageCache) -> Result<SyncReport, ()> {
+    // Take the history ID before listing so that changes made during the
+    // scan are picked up by the next incremental sync
+    let profile = profile_get(token).await?;
+    let mut report = SyncReport {
+        mode: "full".to_string(),
+        history_id: profile.historyId,
+        ..Default::default()
+    };
+
+    let mut listed = HashSet::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let list = messages_list(token, FULL_SCAN_PAGE_SIZE, page_token.as_deref(), None).await?;
+        for entry in list.messages {
+            if cache.get(account, &entry.id).is_none() {
+                if let Ok(msg) = message_get(token, &entry.id).await {
+                    cache.put(account, &CachedMessage::from_message(msg));
+                    report.added += 1;
+                }
+            }
+            listed.insert(entry.id);
+        }
+        match list.nextPageToken {
+            Some(next_token) => page_token = Some(next_token),
+            None => break,
+        }
+    }
+
+    for cached in cache.all(account) {
+        if !listed.contains(&cached.id) {
+            cache.remove(account, &cached.id);
+            report.deleted += 1;
+        }
+    }
+    cache.set_history_id(account, &report.history_id);
+    Ok(report)
+}
@@ -0,0 +1,238 @@
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde::Serialize;
+use std::net::IpAddr;
+
+/// How a list can be unsubscribed from, best option first.
+#[derive(Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsubscribeMethod {
+    /// RFC 8058 one-click: a POST to `url` done by the server.
+    OneClick,
+    /// A web page the user has to open and follow.
+    Link,
+    /// A prefilled email the user has to send.
+    Mailto,
+    None,
+}
+
+/// What unsubscribing from a list would do, shown to the user to confirm.
+#[derive(Serialize)]
+pub struct UnsubscribePlan {
+    pub list_id: String,
+    pub name: String,
+    pub method: UnsubscribeMethod,
+    pub url: String,
+}
+
+/// Extracts the URIs of a `List-Unsubscribe` header value such as
+/// `<mailto:leave@example.com>, <https://example.com/u?id=1>`.
+pub fn parse_list_unsubscribe(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .filter_map(|part| {
+            let part = part.trim();
+            part.strip_prefix('<')
+                .and_then(|part| part.strip_suffix('>'))
+                .map(|uri| uri.trim().to_string())
+        })
+        .filter(|uri| !uri.is_empty())
+        .collect()
+}
+
+/// Returns whether a URI can receive a one-click POST. RFC 8058 requires
+/// HTTPS. The header comes from the sender, so hosts naming this machine
+/// or a private network are refused too.
+fn is_one_click_target(uri: &str) -> bool {
+    let Ok(url) = Url::parse(uri) else {
+        return false;
+    };
+    if url.scheme() != "https" {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(IpAddr::V4(ip)) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let segment = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || segment & 0xfe00 == 0xfc00
+                || segment & 0xffc0 == 0xfe80
+                || ip.to_ipv4_mapped().is_some())
+        }
+        Err(_) => {
+            let host = host.trim_end_matches('.').to_lowercase();
+            host != "localhost" && !host.ends_with(".localhost")
+        }
+    }
+}
+
+/// Picks the unsubscribe method from the `List-Unsubscribe` and
+/// `List-Unsubscribe-Post` headers of a list, preferring one-click, then
+/// a web link, then email.
+pub fn choose_method(
+    list_unsubscribe: &str,
+    list_unsubscribe_post: &str,
+) -> (UnsubscribeMethod, String) {
+    let uris = parse_list_unsubscribe(list_unsubscribe);
+    let one_click = list_unsubscribe_post
+        .trim()
+        .eq_ignore_ascii_case("List-Unsubscribe=One-Click");
+    if one_click {
+        if let Some(uri) = uris.iter().find(|uri| is_one_click_target(uri)) {
+            return (UnsubscribeMethod::OneClick, uri.clone());
+        }
+    }
+    if let Some(uri) = uris
+        .iter()
+        .find(|uri| uri.starts_with("https://") || uri.starts_with("http://"))
+    {
+        return (UnsubscribeMethod::Link, uri.clone());
+    }
+    if let Some(uri) = uris.iter().find(|uri| uri.starts_with("mailto:")) {
+        return (UnsubscribeMethod::Mailto, uri.clone());
+    }
+    (UnsubscribeMethod::None, String::new())
+}
+
+/// Unsubscribes by sending the RFC 8058 one-click POST to `url`. No
+/// cookies or credentials are sent along, and redirects are not followed
+/// so the request cannot be bounced to another host.
+///
+/// # Arguments
+/// * `url` - The HTTPS URI from the `List-Unsubscribe` header.
+///
+/// # Returns
+/// A Result containing the HTTP status code on a 2xx response or an error
+/// on failure.
+pub async fn one_click_unsubscribe(url: &str) -> Result<u16, ()> {
+    if !is_one_click_target(url) {
+        println!("unsubscribe error: {} is not a one-click target", url);
+        return Err(());
+    }
+    send_one_click(url).await
+}
+
+/// Sends the one-click POST to `url` without checking where it points.
+async fn send_one_click(url: &str) -> Result<u16, ()> {
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .map_err(|e| println!("request error: {}", e))?;
+    let result = client
+        .post(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body("List-Unsubscribe=One-Click")
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => Ok(response.status().as_u16()),
+        Ok(response) => {
+            println!("unsubscribe error: status {}", response.status());
+            Err(())
+        }
+        Err(e) => {
+            println!("request error: {}", e);
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Starts a stand-in list server on a loopback port which answers one
+    /// request with `response` and hands back what it received.
+    fn stand_in_server(response: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/unsubscribe?id=42",
+            listener.local_addr().unwrap()
+        );
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"List-Unsubscribe=One-Click") {
+                let read = stream.read(&mut buffer).unwrap();
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn posts_the_one_click_body() {
+        let (url, server) =
+            stand_in_server("HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        assert_eq!(send_one_click(&url).await, Ok(200));
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /unsubscribe?id=42 HTTP/1.1\r\n"));
+        let lower = request.to_lowercase();
+        assert!(lower.contains("content-type: application/x-www-form-urlencoded\r\n"));
+        assert!(!lower.contains("cookie:"));
+        assert!(!lower.contains("authorization:"));
+        assert!(request.ends_with("\r\n\r\nList-Unsubscribe=One-Click"));
+    }
+
+    #[tokio::test]
+    async fn does_not_follow_redirects() {
+        let (url, server) = stand_in_server(
+            "HTTP/1.1 307 Temporary Redirect\r\nLocation: http://127.0.0.1:1/admin\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+        assert_eq!(send_one_click(&url).await, Err(()));
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_local_and_plain_http_targets() {
+        for url in [
+            "http://example.com/u",
+            "http://127.0.0.1:8080/u",
+            "https://localhost/u",
+            "https://127.0.0.1/u",
+            "https://10.0.0.1/u",
+            "https://192.168.1.1/u",
+            "https://[::1]/u",
+            "https://[::ffff:127.0.0.1]/u",
+            "ftp://example.com/u",
+        ] {
+            assert!(!is_one_click_target(url), "{}", url);
+            assert_eq!(one_click_unsubscribe(url).await, Err(()));
+        }
+        assert!(is_one_click_target("https://lists.example.com/u?id=1"));
+    }
+
+    #[test]
+    fn prefers_one_click_over_links() {
+        let (method, url) = choose_method(
+            "<mailto:leave@example.com>, <https://example.com/u?id=1>",
+            "List-Unsubscribe=One-Click",
+        );
+        assert!(method == UnsubscribeMethod::OneClick);
+        assert_eq!(url, "https://example.com/u?id=1");
+
+        let (method, url) = choose_method("<http://127.0.0.1/u>", "List-Unsubscribe=One-Click");
+        assert!(method == UnsubscribeMethod::Link);
+        assert_eq!(url, "http://127.0.0.1/u");
+    }
+}
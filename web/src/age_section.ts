@@ -0,0 +1,106 @@
+import { AgeTotal } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class AgeSectionComponent {
+    private chart: any = null;
+    private canvas: HTMLCanvasElement;
+    private periodSelect: HTMLSelectElement;
+    private summaryElement: HTMLParagraphElement;
+    public onPeriodChange?: (period: string) => void;
+
+    constructor() {
+        this.canvas = document.createElement('canvas');
+        this.canvas.height = 200;
+        this.periodSelect = document.createElement('select');
+        this.summaryElement = document.createElement('p');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        this.periodSelect.addEventListener('change', () => {
+            this.onPeriodChange?.(this.periodSelect.value);
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const headerRow = document.createElement('div');
+        headerRow.className = 'flex items-center justify-between mb-4';
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold';
+        title.textContent = 'Storage by Age';
+
+        const periods = [
+            { value: 'year', label: 'By year' },
+            { value: 'month', label: 'By month' }
+        ];
+        periods.forEach(period => {
+            const option = document.createElement('option');
+            option.value = period.value;
+            option.textContent = period.label;
+            this.periodSelect.appendChild(option);
+        });
+        this.periodSelect.className = 'input-field';
+
+        this.summaryElement.className = 'mt-4 text-gray-600';
+
+        headerRow.appendChild(title);
+        headerRow.appendChild(this.periodSelect);
+        section.appendChild(headerRow);
+        section.appendChild(this.canvas);
+        section.appendChild(this.summaryElement);
+
+        return section;
+    }
+
+    public get period(): string {
+        return this.periodSelect.value;
+    }
+
+    public updateAges(ages: AgeTotal[]): void {
+        // Point out how much would go with an older_than: cleanup of mail
+        // from before the last year shown
+        const olderThanOneYear = ages.slice(0, -1);
+        if (this.period === 'year' && olderThanOneYear.length > 0) {
+            const share = olderThanOneYear.reduce((sum, age) => sum + age.share, 0);
+            const size = olderThanOneYear.reduce((sum, age) => sum + age.total_size, 0);
+            this.summaryElement.textContent = `Mail from before ${ages[ages.length - 1].period} takes up ${formatSize(size)} (${(share * 100).toFixed(1)}%), see the older_than: search operator`;
+        } else {
+            this.summaryElement.textContent = '';
+        }
+
+        const ctx = this.canvas.getContext('2d');
+        if (!ctx) return;
+
+        if (this.chart) {
+            this.chart.destroy();
+        }
+
+        this.chart = new (window as any).Chart(ctx, {
+            type: 'bar',
+            data: {
+                labels: ages.map(age => age.period),
+                datasets: [{
+                    label: 'Size (MB)',
+                    data: ages.map(age => age.total_size / (1024 * 1024)),
+                    backgroundColor: '#3b82f6'
+                }]
+            },
+            options: {
+                responsive: true,
+                plugins: {
+                    legend: {
+                        display: false
+                    },
+                    tooltip: {
+                        callbacks: {
+                            afterLabel: (context: any) => `${(ages[context.dataIndex].share * 100).toFixed(1)}% of scanned storage`
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
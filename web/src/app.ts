@@ -4,17 +4,26 @@ import HeaderComponent from './header.js'
 import SearchSectionComponent from './search.js';
 import ChartSectionComponent from './chart_section.js';
 import TabsSectionComponent from './tab_section.js';
-import {ProfileData, MessageItem, CategorizedMessages} from './types.js';
+import TrendSectionComponent from './trend_section.js';
+import InsightsSectionComponent from './insights_section.js';
+import ThreadSectionComponent from './thread_section.js';
+import SenderSectionComponent from './sender_section.js';
+import AttachmentSectionComponent from './attachment_section.js';
+import DuplicateSectionComponent from './duplicate_section.js';
+import LabelSectionComponent from './label_section.js';
+import AgeSectionComponent from './age_section.js';
+import RuleSectionComponent from './rule_section.js';
+import NewsletterSectionComponent from './newsletter_section.js';
+import CleanupSectionComponent from './cleanup_section.js';
+import FilterSectionComponent from './filter_section.js';
+import {ProfileData, MessageItem, CategorizedMessages, Snapshot, SnapshotDiff, ThreadTotal, SenderTotal, AttachmentEntry, KindTotal, DuplicateReport, LabelTotal, AgeTotal, SizeBucket, SizeClassifier, Rule, CategoryTotal, ListTotal, UnsubscribePlan, BulkSelection, BulkTrashReport, JournalEntry, UndoReport, FilterRequest, FilterPreview, SaveReport, ArchiveReport, StripReport} from './types.js';
 
 export class App {
     private profileData: ProfileData | null = null;
     private messages: MessageItem[] = [];
-    private categorizedMessages: CategorizedMessages = {
-        small: [],
-        medium: [],
-        large: []
-    };
-    private currentTab: string = 'small';
+    private buckets: SizeBucket[] = [];
+    private categorizedMessages: CategorizedMessages = {};
+    private currentTab: string = '';
     private currentView: 'home' | 'about' = 'home';
 
     private rootContainer: RootContainer;
@@ -22,8 +31,22 @@ export class App {
     private searchSection: SearchSectionComponent;
     private chartSection: ChartSectionComponent;
     private tabsSection: TabsSectionComponent;
+    private trendSection: TrendSectionComponent;
+    private insightsSection: InsightsSectionComponent;
+    private threadSection: ThreadSectionComponent;
+    private senderSection: SenderSectionComponent;
+    private attachmentSection: AttachmentSectionComponent;
+    private duplicateSection: DuplicateSectionComponent;
+    private labelSection: LabelSectionComponent;
+    private ageSection: AgeSectionComponent;
+    private ruleSection: RuleSectionComponent;
+    private newsletterSection: NewsletterSectionComponent;
+    private cleanupSection: CleanupSectionComponent;
+    private filterSection: FilterSectionComponent;
     private chartElement: HTMLDivElement | null = null;
     private tabsElement: HTMLDivElement | null = null;
+    private trendElement: HTMLDivElement | null = null;
+    private insightsElement: HTMLDivElement | null = null;
     private homeDiv: HTMLDivElement | null = null;
     private aboutDiv: HTMLDivElement | null = null;
 
@@ -32,6 +55,18 @@ export class App {
         this.searchSection = new SearchSectionComponent();
         this.chartSection = new ChartSectionComponent();
         this.tabsSection = new TabsSectionComponent();
+        this.trendSection = new TrendSectionComponent();
+        this.insightsSection = new InsightsSectionComponent();
+        this.threadSection = new ThreadSectionComponent();
+        this.senderSection = new SenderSectionComponent();
+        this.attachmentSection = new AttachmentSectionComponent();
+        this.duplicateSection = new DuplicateSectionComponent();
+        this.labelSection = new LabelSectionComponent();
+        this.ageSection = new AgeSectionComponent();
+        this.ruleSection = new RuleSectionComponent();
+        this.newsletterSection = new NewsletterSectionComponent();
+        this.cleanupSection = new CleanupSectionComponent();
+        this.filterSection = new FilterSectionComponent();
         this.rootContainer = new RootContainer();
 
         this.initializeApp();
@@ -41,6 +76,209 @@ export class App {
         this.loadProfile();
         this.renderApp();
         this.setupEventListeners();
+        this.loadBuckets();
+    }
+
+    private async loadPermissions(): Promise<void> {
+        try {
+            const response = await fetch('/api/permissions');
+            const permissions: { modify: boolean, filters: boolean } = await response.json();
+            this.headerComponent.setCanModify(permissions.modify);
+            this.tabsSection.setCanModify(permissions.modify);
+            this.cleanupSection.setCanModify(permissions.modify);
+            this.filterSection.setCanModify(permissions.filters);
+            this.attachmentSection.setCanModify(permissions.modify);
+            this.loadJournal();
+        } catch (error) {
+            console.error('Permissions fetch error:', error);
+        }
+    }
+
+    private async trashMessages(messageIds: string[]): Promise<void> {
+        try {
+            const response = await fetch('/api/trash', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ message_ids: messageIds })
+            });
+            if (!response.ok) {
+                alert(await response.text());
+                return;
+            }
+            const report: { trashed: string[], failed: string[] } = await response.json();
+            const trashed = new Set(report.trashed);
+            this.messages = this.messages.filter(item => !trashed.has(item.id));
+            if (report.failed.length > 0) {
+                alert(`${report.failed.length} messages could not be moved to the Trash`);
+            }
+            this.loadJournal();
+        } catch (error) {
+            console.error('Trash error:', error);
+        }
+        this.refreshResults();
+    }
+
+    private async archiveMessages(messageIds: string[]): Promise<void> {
+        await this.modifyMessages('/api/archive', { message_ids: messageIds });
+    }
+
+    private async labelMessages(messageIds: string[], label: string): Promise<void> {
+        await this.modifyMessages('/api/label', { message_ids: messageIds, label });
+    }
+
+    private async modifyMessages(url: string, body: object): Promise<void> {
+        try {
+            const response = await fetch(url, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify(body)
+            });
+            if (!response.ok) {
+                alert(await response.text());
+                return;
+            }
+            const report: { modified: string[], failed: string[] } = await response.json();
+            if (report.failed.length > 0) {
+                alert(`${report.failed.length} messages could not be changed`);
+            }
+            this.loadJournal();
+            this.loadLabels();
+        } catch (error) {
+            console.error('Modify error:', error);
+        }
+    }
+
+    private async bulkTrash(selection: BulkSelection, dryRun: boolean, messageIds: string[]): Promise<BulkTrashReport | string> {
+        try {
+            const response = await fetch('/api/trash/bulk', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ ...selection, dry_run: dryRun, message_ids: messageIds })
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            const report: BulkTrashReport = await response.json();
+            if (!dryRun) {
+                const response = await fetch('/api/messages');
+                const cached: MessageItem[] = await response.json();
+                const remaining = new Set(cached.map(item => item.id));
+                this.messages = this.messages.filter(item => remaining.has(item.id));
+                this.refreshResults();
+                this.loadJournal();
+            }
+            return report;
+        } catch (error) {
+            console.error('Bulk trash error:', error);
+            return 'Error moving messages to the Trash';
+        }
+    }
+
+    private async createFilter(request: FilterRequest, dryRun: boolean): Promise<FilterPreview | string> {
+        try {
+            const response = await fetch('/api/filters', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ ...request, dry_run: dryRun })
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            return await response.json();
+        } catch (error) {
+            console.error('Filter error:', error);
+            return 'Error creating the filter';
+        }
+    }
+
+    private async exportMessages(selection: BulkSelection, format: string, folder: string): Promise<ArchiveReport | string> {
+        try {
+            const response = await fetch('/api/export/messages', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ ...selection, format, folder: folder || null })
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            return await response.json();
+        } catch (error) {
+            console.error('Export error:', error);
+            return 'Error exporting the messages';
+        }
+    }
+
+    private async loadJournal(): Promise<void> {
+        try {
+            const response = await fetch('/api/journal');
+            const entries: JournalEntry[] = await response.json();
+            this.cleanupSection.updateJournal(entries);
+        } catch (error) {
+            console.error('Journal fetch error:', error);
+        }
+    }
+
+    private async undo(entryId: number): Promise<string> {
+        try {
+            const response = await fetch(`/api/undo?id=${entryId}`, { method: 'POST' });
+            if (!response.ok) {
+                return await response.text();
+            }
+            const report: UndoReport = await response.json();
+            this.loadJournal();
+            return report.failed > 0
+                ? `Restored ${report.restored}, ${report.failed} failed`
+                : `Restored ${report.restored}`;
+        } catch (error) {
+            console.error('Undo error:', error);
+            return 'Error undoing';
+        }
+    }
+
+    private async loadBuckets(): Promise<void> {
+        try {
+            const response = await fetch('/api/settings/buckets');
+            const classifier: SizeClassifier = await response.json();
+            this.setBuckets(classifier.buckets);
+        } catch (error) {
+            console.error('Buckets fetch error:', error);
+        }
+    }
+
+    private setBuckets(buckets: SizeBucket[]): void {
+        this.buckets = buckets;
+        this.tabsSection.setBuckets(buckets.map(bucket => bucket.name));
+        this.cleanupSection.setBuckets(buckets.map(bucket => bucket.name));
+        this.chartSection.setBuckets(buckets);
+    }
+
+    private async saveBuckets(buckets: SizeBucket[]): Promise<string | null> {
+        try {
+            const response = await fetch('/api/settings/buckets', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ buckets })
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            const classifier: SizeClassifier = await response.json();
+            this.setBuckets(classifier.buckets);
+
+            // Replace the messages already shown with the server's
+            // classification under the new buckets
+            if (this.messages.length > 0) {
+                const messagesResponse = await fetch('/api/messages');
+                const cached: MessageItem[] = await messagesResponse.json();
+                const byId = new Map(cached.map(item => [item.id, item] as [string, MessageItem]));
+                this.messages = this.messages.map(item => byId.get(item.id) || item);
+                this.refreshResults();
+            }
+            return null;
+        } catch (error) {
+            console.error('Buckets save error:', error);
+            return 'Error saving size classifications';
+        }
     }
 
     private async loadProfile(): Promise<void> {
@@ -49,6 +287,7 @@ export class App {
             this.profileData = await response.json();
             if (this.profileData && this.profileData.email !== "") {
                 this.headerComponent.updateProfile(this.profileData);
+                this.loadPermissions();
             }
         } catch (error) {
             console.error('Profile fetch error:', error);
@@ -100,9 +339,23 @@ export class App {
         const searchElement = this.searchSection.render();
         this.chartElement = this.chartSection.render();
         this.tabsElement = this.tabsSection.render();
+        this.trendElement = this.trendSection.render();
+        this.insightsElement = this.insightsSection.render();
+        this.insightsSection.addTab('labels', 'Labels', this.labelSection.render());
+        this.insightsSection.addTab('age', 'Age', this.ageSection.render());
+        this.insightsSection.addTab('senders', 'Senders', this.senderSection.render());
+        this.insightsSection.addTab('newsletters', 'Newsletters', this.newsletterSection.render());
+        this.insightsSection.addTab('threads', 'Threads', this.threadSection.render());
+        this.insightsSection.addTab('attachments', 'Attachments', this.attachmentSection.render());
+        this.insightsSection.addTab('duplicates', 'Duplicates', this.duplicateSection.render());
+        this.insightsSection.addTab('rules', 'Rules', this.ruleSection.render());
+        this.insightsSection.addTab('cleanup', 'Cleanup', this.cleanupSection.render());
+        this.insightsSection.addTab('filters', 'Filters', this.filterSection.render());
 
         this.chartElement.style.display = "none";
         this.tabsElement.style.display = "none";
+        this.trendElement.style.display = "none";
+        this.insightsElement.style.display = "none";
 
         // Create main content
         const mainContent = document.createElement('div');
@@ -117,6 +370,8 @@ export class App {
         this.homeDiv.appendChild(searchElement);
         this.homeDiv.appendChild(this.chartElement);
         this.homeDiv.appendChild(this.tabsElement);
+        this.homeDiv.appendChild(this.insightsElement);
+        this.homeDiv.appendChild(this.trendElement);
 
         // Create about view
         this.aboutDiv = document.createElement('div');
@@ -125,7 +380,8 @@ export class App {
         this.aboutDiv.innerHTML = `
             <div class="about-container p-8">
                 <h1 class="text-3xl font-bold mb-4">About</h1>
-                <p class="mb-4">Gmail Cleaner is a desktop app which helps you find out which emails are taking up storage space in your Gmail account. This app runs on your desktop and does not send your email to any server. Therefore it is the most secure way of cleaning up your Gmail account. You don't need to give any permissions to this app, neither do you need to provide credentials. When you start it, a browser window opens up with Gmail's login page. Once Gmail authorizes you the app visually shows you what you can delete. The app does not read your email or cannot modify your Gmail account in any way.</p>
+                <p class="mb-4">Gmail Cleaner is a desktop app which helps you find out which emails are taking up storage space in your Gmail account. This app runs on your desktop and does not send your email to any server. Therefore it is the most secure way of cleaning up your Gmail account. You don't need to give any permissions to this app, neither do you need to provide credentials. When you start it, a browser window opens up with Gmail's login page. Once Gmail authorizes you the app visually shows you what you can delete. By default the app only gets read-only access and cannot modify your Gmail account in any way.</p>
+                <p class="mb-4">If you choose Enable cleanup, Gmail asks you to also let the app modify your mailbox, so that it can move the messages you select to the Trash. Creating filters needs more: if you choose Allow filters, Gmail also asks you to let the app change its settings, so that it can create the filters you set up. Nothing is changed unless you ask for it.</p>
                 
                 <p class="mb-4">&copy; 2025 <a href="https://eastcoastsoft.com/products/gmail-cleaner/" target="_blank">East Coast Software LLC</a></p>
             </div>
@@ -135,19 +391,44 @@ export class App {
 
     private setupEventListeners(): void {
         this.searchSection.onSearch = this.performSearch.bind(this);
+        this.searchSection.onSync = this.syncMailbox.bind(this);
+        this.trendSection.onCompare = this.compareSnapshots.bind(this);
+        this.threadSection.onInspect = this.inspectThread.bind(this);
+        this.senderSection.onGroupChange = this.loadSenders.bind(this);
+        this.senderSection.onDrillDown = this.loadSenderMessages.bind(this);
+        this.ageSection.onPeriodChange = this.loadAges.bind(this);
+        this.attachmentSection.onFilterChange = this.loadAttachments.bind(this);
+        this.attachmentSection.onSave = this.saveAttachments.bind(this);
+        this.attachmentSection.onStrip = this.stripAttachments.bind(this);
+        this.attachmentSection.onTrash = async (messageIds: string[]) => {
+            await this.trashMessages(messageIds);
+            this.loadAttachments(this.attachmentSection.sort, this.attachmentSection.kind);
+        };
+        this.duplicateSection.onVerify = () => this.loadDuplicates(true);
+        this.newsletterSection.onUnsubscribe = this.unsubscribe.bind(this);
+        this.ruleSection.onSaveRules = this.saveRules.bind(this);
+        this.ruleSection.onDrillDown = this.loadRuleMessages.bind(this);
         this.tabsSection.onTabChange = this.switchTab.bind(this);
+        this.tabsSection.onTrash = this.trashMessages.bind(this);
+        this.tabsSection.onArchive = this.archiveMessages.bind(this);
+        this.tabsSection.onLabel = this.labelMessages.bind(this);
+        this.cleanupSection.onRun = this.bulkTrash.bind(this);
+        this.cleanupSection.onUndo = this.undo.bind(this);
+        this.cleanupSection.onExport = this.exportMessages.bind(this);
+        this.filterSection.onRun = this.createFilter.bind(this);
+        this.chartSection.onSaveBuckets = this.saveBuckets.bind(this);
         this.headerComponent.onShowAbout = this.showAbout.bind(this);
         this.headerComponent.onShowHome = this.showHome.bind(this);
 
         this.headerComponent.setupEventListeners();
     }
 
-    private async performSearch(maxMessages: number): Promise<void> {
+    private async performSearch(maxMessages: number, strategy: string): Promise<void> {
         this.searchSection.setLoading(true);
 
         try {
-            const response = await fetch(`/api/summary?max=${encodeURIComponent(maxMessages.toString())}`);
-            const newMessages = await response.json();
+            const response = await fetch(`/api/summary?max=${encodeURIComponent(maxMessages.toString())}&strategy=${encodeURIComponent(strategy)}`);
+            const newMessages: MessageItem[] = await response.json();
 
             if (this.chartElement) {
                 this.chartElement.style.display = "grid";
@@ -156,38 +437,351 @@ export class App {
                 this.tabsElement.style.display = "grid";
             }
             
-            // Append new messages to existing ones
-            this.messages.push(...newMessages);
-
-            // Re-categorize all messages
-            this.categorizedMessages = {
-                small: this.messages.filter(item => item.size < 100 * 1024),
-                medium: this.messages.filter(item => item.size >= 100 * 1024 && item.size < 1024 * 1024),
-                large: this.messages.filter(item => item.size >= 1024 * 1024)
-            };
+            // Append new messages to existing ones, skipping any already
+            // found by the other scan strategy
+            const knownIds = new Set(this.messages.map(item => item.id));
+            this.messages.push(...newMessages.filter(item => !knownIds.has(item.id)));
+
+            this.refreshResults();
+
+        } catch (error) {
+            console.error('Error:', error);
+            this.tabsSection.showError('Error fetching results');
+        } finally {
+            this.searchSection.setLoading(false);
+        }
+    }
 
-            // Update tabs with all results
-            this.tabsSection.clearAllResults();
-            this.tabsSection.updateResults(this.categorizedMessages);
+    private async syncMailbox(): Promise<void> {
+        this.searchSection.setLoading(true);
 
-            // Update UI components
-            this.chartSection.updateChart(this.categorizedMessages);
-            this.tabsSection.updateStats(this.categorizedMessages);
+        try {
+            const syncResponse = await fetch('/api/sync', { method: 'POST' });
+            const report = await syncResponse.json();
+            if (!report) {
+                throw new Error('Sync failed');
+            }
 
-            // Update analyzed count
-            this.searchSection.updateAnalyzedCount(this.messages.length);
+            const response = await fetch('/api/messages');
+            this.messages = await response.json();
 
-            // Switch to small tab by default
-            this.switchTab('small');
+            if (this.chartElement) {
+                this.chartElement.style.display = "grid";
+            }
+            if (this.tabsElement) {
+                this.tabsElement.style.display = "grid";
+            }
+
+            this.refreshResults();
 
         } catch (error) {
             console.error('Error:', error);
-            this.tabsSection.showError('Error fetching results');
+            this.tabsSection.showError('Error syncing mailbox');
         } finally {
             this.searchSection.setLoading(false);
         }
     }
 
+    private refreshResults(): void {
+        // Re-categorize all messages by the bucket the server assigned
+        this.categorizedMessages = {};
+        this.buckets.forEach(bucket => {
+            this.categorizedMessages[bucket.name] = [];
+        });
+        this.messages.forEach(item => {
+            this.categorizedMessages[item.bucket]?.push(item);
+        });
+
+        // Update tabs with all results
+        this.tabsSection.clearAllResults();
+        this.tabsSection.updateResults(this.categorizedMessages);
+
+        // Update UI components
+        this.chartSection.updateChart(this.categorizedMessages);
+        this.tabsSection.updateStats(this.categorizedMessages);
+
+        // Update analyzed count
+        this.searchSection.updateAnalyzedCount(this.messages.length);
+
+        // Stay on the current tab, or switch to the smallest bucket
+        const names = this.buckets.map(bucket => bucket.name);
+        this.switchTab(names.includes(this.currentTab) ? this.currentTab : names[0] ?? '');
+
+        this.loadSnapshots();
+        this.loadInsights();
+    }
+
+    private async loadInsights(): Promise<void> {
+        if (this.insightsElement) {
+            this.insightsElement.style.display = "flex";
+        }
+
+        this.loadLabels();
+        this.loadAges(this.ageSection.period);
+        this.loadSenders(this.senderSection.group);
+        this.loadNewsletters();
+        this.loadAttachmentTotals();
+        this.loadAttachments(this.attachmentSection.sort, this.attachmentSection.kind);
+        this.loadDuplicates(false);
+        this.loadRules();
+
+        try {
+            const response = await fetch('/api/threads');
+            const threads: ThreadTotal[] = await response.json();
+            this.threadSection.updateThreads(threads);
+        } catch (error) {
+            console.error('Threads fetch error:', error);
+        }
+    }
+
+    private async loadLabels(): Promise<void> {
+        try {
+            const response = await fetch('/api/labels');
+            const labels: LabelTotal[] = await response.json();
+            this.labelSection.updateLabels(labels);
+        } catch (error) {
+            console.error('Labels fetch error:', error);
+        }
+    }
+
+    private async loadAges(period: string): Promise<void> {
+        try {
+            const response = await fetch(`/api/age?period=${encodeURIComponent(period)}`);
+            const ages: AgeTotal[] = await response.json();
+            this.ageSection.updateAges(ages);
+        } catch (error) {
+            console.error('Age fetch error:', error);
+        }
+    }
+
+    private async loadSenders(group: string): Promise<void> {
+        try {
+            const response = await fetch(`/api/senders?group=${encodeURIComponent(group)}`);
+            const senders: SenderTotal[] = await response.json();
+            this.senderSection.updateSenders(senders);
+        } catch (error) {
+            console.error('Senders fetch error:', error);
+        }
+    }
+
+    private async loadNewsletters(): Promise<void> {
+        try {
+            const response = await fetch('/api/newsletters');
+            const lists: ListTotal[] = await response.json();
+            this.newsletterSection.updateLists(lists);
+        } catch (error) {
+            console.error('Newsletters fetch error:', error);
+        }
+    }
+
+    private async unsubscribe(list: ListTotal): Promise<string> {
+        try {
+            const listParam = `list_id=${encodeURIComponent(list.list_id)}`;
+            const previewResponse = await fetch(`/api/unsubscribe?${listParam}`);
+            const plan: UnsubscribePlan | null = await previewResponse.json();
+            if (!plan || plan.method === 'none') {
+                return 'This list does not say how to unsubscribe';
+            }
+
+            const descriptions = {
+                one_click: `send an unsubscribe request to ${plan.url}`,
+                link: `open the unsubscribe page ${plan.url}`,
+                mailto: `open an unsubscribe email to ${plan.url.replace(/^mailto:/, '')}`
+            };
+            if (!confirm(`Unsubscribe from ${plan.name || plan.list_id}? This will ${descriptions[plan.method]}.`)) {
+                return '';
+            }
+
+            const response = await fetch(`/api/unsubscribe?${listParam}`, { method: 'POST' });
+            if (!response.ok) {
+                return await response.text();
+            }
+            if (plan.method === 'one_click') {
+                return 'Unsubscribed';
+            }
+            window.open(plan.url, '_blank');
+            return plan.method === 'link' ? 'Opened the unsubscribe page' : 'Opened the unsubscribe email';
+        } catch (error) {
+            console.error('Unsubscribe error:', error);
+            return 'Error unsubscribing';
+        }
+    }
+
+    private async loadAttachmentTotals(): Promise<void> {
+        try {
+            const response = await fetch('/api/attachments/types');
+            const totals: KindTotal[] = await response.json();
+            this.attachmentSection.updateTotals(totals);
+        } catch (error) {
+            console.error('Attachment totals fetch error:', error);
+        }
+    }
+
+    private async loadAttachments(sort: string, kind: string): Promise<void> {
+        try {
+            const kindParam = kind ? `&kind=${encodeURIComponent(kind)}` : '';
+            const response = await fetch(`/api/attachments?sort=${encodeURIComponent(sort)}${kindParam}`);
+            const attachments: AttachmentEntry[] = await response.json();
+            this.attachmentSection.updateAttachments(attachments);
+        } catch (error) {
+            console.error('Attachments fetch error:', error);
+        }
+    }
+
+    private async saveAttachments(attachments: AttachmentEntry[], folder: string): Promise<SaveReport | string> {
+        try {
+            const response = await fetch('/api/attachments/save', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({
+                    attachments: attachments.map(item => ({ message_id: item.message_id, part_id: item.part_id })),
+                    folder: folder || null
+                })
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            return await response.json();
+        } catch (error) {
+            console.error('Save attachments error:', error);
+            return 'Error saving the attachments';
+        }
+    }
+
+    private async stripAttachments(attachments: AttachmentEntry[], save: boolean, folder: string): Promise<StripReport | string> {
+        try {
+            const response = await fetch('/api/attachments/strip', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({
+                    attachments: attachments.map(item => ({ message_id: item.message_id, part_id: item.part_id })),
+                    save,
+                    folder: folder || null
+                })
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            const report: StripReport = await response.json();
+            this.loadAttachments(this.attachmentSection.sort, this.attachmentSection.kind);
+            this.loadJournal();
+            return report;
+        } catch (error) {
+            console.error('Strip attachments error:', error);
+            return 'Error removing the attachments';
+        }
+    }
+
+    private async loadDuplicates(verify: boolean): Promise<void> {
+        this.duplicateSection.setLoading(true);
+        try {
+            const response = await fetch(`/api/duplicates?verify=${verify}`);
+            const report: DuplicateReport = await response.json();
+            this.duplicateSection.updateReport(report);
+        } catch (error) {
+            console.error('Duplicates fetch error:', error);
+        } finally {
+            this.duplicateSection.setLoading(false);
+        }
+    }
+
+    private async loadRules(): Promise<void> {
+        try {
+            const response = await fetch('/api/rules');
+            const rules: Rule[] = await response.json();
+            this.ruleSection.setRules(rules);
+            this.loadRuleCategories();
+        } catch (error) {
+            console.error('Rules fetch error:', error);
+        }
+    }
+
+    private async loadRuleCategories(): Promise<void> {
+        try {
+            const response = await fetch('/api/rules/categories');
+            const categories: CategoryTotal[] = await response.json();
+            this.ruleSection.updateCategories(categories);
+        } catch (error) {
+            console.error('Rule categories fetch error:', error);
+        }
+    }
+
+    private async saveRules(rules: Rule[]): Promise<string | null> {
+        try {
+            const response = await fetch('/api/rules', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify(rules)
+            });
+            if (!response.ok) {
+                return await response.text();
+            }
+            this.loadRuleCategories();
+            return null;
+        } catch (error) {
+            console.error('Rules save error:', error);
+            return 'Error saving rules';
+        }
+    }
+
+    private async loadRuleMessages(category: string): Promise<MessageItem[]> {
+        try {
+            const response = await fetch(`/api/rules/messages?category=${encodeURIComponent(category)}`);
+            return await response.json();
+        } catch (error) {
+            console.error('Rule messages fetch error:', error);
+            return [];
+        }
+    }
+
+    private async loadSenderMessages(sender: string, group: string): Promise<MessageItem[]> {
+        try {
+            const response = await fetch(`/api/senders/messages?sender=${encodeURIComponent(sender)}&group=${encodeURIComponent(group)}`);
+            return await response.json();
+        } catch (error) {
+            console.error('Sender messages fetch error:', error);
+            return [];
+        }
+    }
+
+    private async inspectThread(threadId: string): Promise<ThreadTotal | null> {
+        try {
+            const response = await fetch(`/api/threads/${encodeURIComponent(threadId)}`);
+            return await response.json();
+        } catch (error) {
+            console.error('Thread fetch error:', error);
+            return null;
+        }
+    }
+
+    private async loadSnapshots(): Promise<void> {
+        try {
+            const response = await fetch('/api/snapshots');
+            const snapshots: Snapshot[] = await response.json();
+            if (snapshots.length === 0) return;
+
+            if (this.trendElement) {
+                this.trendElement.style.display = "block";
+            }
+            this.trendSection.updateSnapshots(snapshots);
+            this.trendSection.showLatest(snapshots[snapshots.length - 1]);
+        } catch (error) {
+            console.error('Snapshots fetch error:', error);
+        }
+    }
+
+    private async compareSnapshots(from: number, to: number): Promise<void> {
+        try {
+            const response = await fetch(`/api/snapshots/diff?from=${from}&to=${to}`);
+            const diff: SnapshotDiff | null = await response.json();
+            if (diff) {
+                this.trendSection.showDiff(diff);
+            }
+        } catch (error) {
+            console.error('Snapshot diff error:', error);
+        }
+    }
+
     private switchTab(category: string): void {
         this.currentTab = category;
         this.tabsSection.switchToTab(category);
@@ -0,0 +1,277 @@
+import { AttachmentEntry, KindTotal, SaveReport, StripReport } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class AttachmentSectionComponent {
+    private sortSelect: HTMLSelectElement;
+    private kindSelect: HTMLSelectElement;
+    private totalsList: HTMLUListElement;
+    private tableBody: HTMLTableSectionElement;
+    private folderInput: HTMLInputElement;
+    private saveButton: HTMLButtonElement;
+    private trashButton: HTMLButtonElement;
+    private stripButton: HTMLButtonElement;
+    private keepCopyCheckbox: HTMLInputElement;
+    private keepCopyLabel: HTMLLabelElement;
+    private statusElement: HTMLParagraphElement;
+    private selected: Map<string, AttachmentEntry> = new Map();
+    private savedMessageIds: string[] = [];
+    private canModify: boolean = false;
+    public onFilterChange?: (sort: string, kind: string) => void;
+    public onSave?: (attachments: AttachmentEntry[], folder: string) => Promise<SaveReport | string>;
+    public onTrash?: (messageIds: string[]) => Promise<void>;
+    public onStrip?: (attachments: AttachmentEntry[], save: boolean, folder: string) => Promise<StripReport | string>;
+
+    constructor() {
+        this.sortSelect = document.createElement('select');
+        this.kindSelect = document.createElement('select');
+        this.totalsList = document.createElement('ul');
+        this.tableBody = document.createElement('tbody');
+        this.folderInput = document.createElement('input');
+        this.saveButton = document.createElement('button');
+        this.trashButton = document.createElement('button');
+        this.stripButton = document.createElement('button');
+        this.keepCopyCheckbox = document.createElement('input');
+        this.keepCopyLabel = document.createElement('label');
+        this.statusElement = document.createElement('p');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        [this.sortSelect, this.kindSelect].forEach(select => {
+            select.addEventListener('change', () => {
+                this.onFilterChange?.(this.sort, this.kind);
+            });
+        });
+
+        this.saveButton.addEventListener('click', async () => {
+            if (this.selected.size === 0) return;
+            this.saveButton.disabled = true;
+            this.statusElement.textContent = `Saving ${this.selected.size} attachments...`;
+            const result = await this.onSave?.(Array.from(this.selected.values()), this.folderInput.value.trim());
+            this.saveButton.disabled = false;
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.statusElement.textContent = result;
+                return;
+            }
+            this.statusElement.textContent = `Saved ${result.saved.length} attachments to ${result.folder}, listed in ${result.manifest}` +
+                (result.failed.length > 0 ? `. ${result.failed.length} could not be saved` : '');
+            // Only messages whose selected attachments were all saved are safe to trash
+            const failed = new Set(result.failed.map(key => key.split('/')[0]));
+            this.savedMessageIds = Array.from(new Set(result.saved.map(entry => entry.message_id)))
+                .filter(id => !failed.has(id));
+            this.trashButton.style.display = this.canModify && this.savedMessageIds.length > 0 ? 'inline-block' : 'none';
+        });
+
+        this.trashButton.addEventListener('click', async () => {
+            if (this.savedMessageIds.length === 0) return;
+            if (!confirm(`Move the ${this.savedMessageIds.length} messages of the saved attachments to the Trash?`)) {
+                return;
+            }
+            await this.onTrash?.(this.savedMessageIds);
+            this.savedMessageIds = [];
+            this.trashButton.style.display = 'none';
+        });
+
+        this.stripButton.addEventListener('click', async () => {
+            if (this.selected.size === 0) return;
+            const keepCopy = this.keepCopyCheckbox.checked;
+            if (!confirm(`Remove ${this.selected.size} attachments from their messages? ` +
+                'Each message is replaced by a copy without them and the original is moved to the Trash.' +
+                (keepCopy ? ' The attachments are saved first.' : ''))) {
+                return;
+            }
+            this.stripButton.disabled = true;
+            this.statusElement.textContent = `Removing ${this.selected.size} attachments...`;
+            const result = await this.onStrip?.(Array.from(this.selected.values()), keepCopy, this.folderInput.value.trim());
+            this.stripButton.disabled = false;
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.statusElement.textContent = result;
+                return;
+            }
+            const removed = result.stripped.reduce((count, message) => count + message.removed.length, 0);
+            this.statusElement.textContent = `Removed ${removed} attachments from ${result.stripped.length} messages` +
+                (result.failed.length > 0 ? `, ${result.failed.length} messages could not be changed` : '') +
+                (result.unchanged.length > 0 ? `, ${result.unchanged.length} messages had no such attachments` : '');
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Attachments';
+
+        this.totalsList.className = 'list-disc pl-6 mb-4';
+
+        const sorts = [
+            { value: 'size', label: 'Largest first' },
+            { value: 'name', label: 'By file name' },
+            { value: 'type', label: 'By MIME type' }
+        ];
+        sorts.forEach(sort => {
+            const option = document.createElement('option');
+            option.value = sort.value;
+            option.textContent = sort.label;
+            this.sortSelect.appendChild(option);
+        });
+        this.sortSelect.className = 'input-field';
+        this.kindSelect.className = 'input-field';
+        this.setKinds([]);
+
+        const filters = document.createElement('div');
+        filters.className = 'flex gap-3 flex-row mb-4';
+        filters.appendChild(this.sortSelect);
+        filters.appendChild(this.kindSelect);
+
+        this.folderInput.className = 'input-field flex-1 p-2';
+        this.folderInput.placeholder = 'Folder to save to, Downloads if empty';
+
+        this.saveButton.className = 'btn-primary';
+        this.saveButton.textContent = 'Save Selected';
+        this.saveButton.disabled = true;
+
+        this.trashButton.className = 'btn-primary';
+        this.trashButton.textContent = 'Move Their Messages to Trash';
+        this.trashButton.style.display = 'none';
+
+        this.stripButton.className = 'btn-primary';
+        this.stripButton.textContent = 'Remove From Messages';
+        this.stripButton.disabled = true;
+        this.stripButton.style.display = 'none';
+
+        this.keepCopyCheckbox.type = 'checkbox';
+        this.keepCopyCheckbox.checked = true;
+        this.keepCopyLabel.className = 'text-sm whitespace-nowrap';
+        this.keepCopyLabel.style.display = 'none';
+        this.keepCopyLabel.appendChild(this.keepCopyCheckbox);
+        this.keepCopyLabel.appendChild(document.createTextNode(' Save first'));
+
+        const actions = document.createElement('div');
+        actions.className = 'flex gap-3 flex-row items-center mb-2';
+        actions.appendChild(this.folderInput);
+        actions.appendChild(this.saveButton);
+        actions.appendChild(this.trashButton);
+        actions.appendChild(this.stripButton);
+        actions.appendChild(this.keepCopyLabel);
+
+        this.statusElement.className = 'text-sm text-gray-600 mb-4';
+
+        const table = document.createElement('table');
+        table.className = 'w-full text-left';
+        const head = document.createElement('thead');
+        head.innerHTML = `
+            <tr>
+                <th class="py-2"></th>
+                <th class="py-2">File</th>
+                <th class="py-2">Type</th>
+                <th class="py-2">Message</th>
+                <th class="py-2 text-right">Size</th>
+            </tr>
+        `;
+        table.appendChild(head);
+        table.appendChild(this.tableBody);
+
+        section.appendChild(title);
+        section.appendChild(this.totalsList);
+        section.appendChild(filters);
+        section.appendChild(actions);
+        section.appendChild(this.statusElement);
+        section.appendChild(table);
+
+        return section;
+    }
+
+    public get sort(): string {
+        return this.sortSelect.value;
+    }
+
+    public get kind(): string {
+        return this.kindSelect.value;
+    }
+
+    private setKinds(kinds: string[]): void {
+        const selected = this.kindSelect.value;
+        this.kindSelect.innerHTML = '';
+        ['', ...kinds].forEach(kind => {
+            const option = document.createElement('option');
+            option.value = kind;
+            option.textContent = kind === '' ? 'All types' : kind;
+            this.kindSelect.appendChild(option);
+        });
+        this.kindSelect.value = kinds.includes(selected) ? selected : '';
+    }
+
+    public updateTotals(totals: KindTotal[]): void {
+        this.totalsList.innerHTML = '';
+        totals.forEach(total => {
+            const li = document.createElement('li');
+            li.innerHTML = `<span class="font-medium">${total.kind}:</span> ${total.count} files, ${formatSize(total.total_size)}`;
+            this.totalsList.appendChild(li);
+        });
+        this.setKinds(totals.map(total => total.kind));
+    }
+
+    public setCanModify(modify: boolean): void {
+        this.canModify = modify;
+        this.stripButton.style.display = modify ? 'inline-block' : 'none';
+        this.keepCopyLabel.style.display = modify ? 'inline' : 'none';
+    }
+
+    public updateAttachments(attachments: AttachmentEntry[]): void {
+        this.tableBody.innerHTML = '';
+        this.selected.clear();
+        this.saveButton.disabled = true;
+        this.stripButton.disabled = true;
+        attachments.forEach(attachment => {
+            const row = document.createElement('tr');
+            row.className = 'border-t';
+
+            const select = document.createElement('td');
+            select.className = 'py-2';
+            const checkbox = document.createElement('input');
+            checkbox.type = 'checkbox';
+            checkbox.addEventListener('change', () => {
+                const key = `${attachment.message_id}/${attachment.part_id}`;
+                if (checkbox.checked) {
+                    this.selected.set(key, attachment);
+                } else {
+                    this.selected.delete(key);
+                }
+                this.saveButton.disabled = this.selected.size === 0;
+                this.stripButton.disabled = this.selected.size === 0;
+            });
+            select.appendChild(checkbox);
+
+            const file = document.createElement('td');
+            file.className = 'py-2 truncate';
+            file.textContent = attachment.filename;
+
+            const type = document.createElement('td');
+            type.className = 'py-2 text-gray-600';
+            type.textContent = attachment.mime_type;
+
+            const message = document.createElement('td');
+            message.className = 'py-2 truncate';
+            const link = document.createElement('a');
+            link.className = 'text-gray-900 hover:text-blue-600 transition-colors';
+            link.href = `https://mail.google.com/mail/u/0/#all/${attachment.thread_id}`;
+            link.target = '_blank';
+            link.textContent = attachment.subject;
+            message.appendChild(link);
+
+            const size = document.createElement('td');
+            size.className = 'py-2 text-right';
+            size.textContent = formatSize(attachment.size);
+
+            row.appendChild(select);
+            row.appendChild(file);
+            row.appendChild(type);
+            row.appendChild(message);
+            row.appendChild(size);
+            this.tableBody.appendChild(row);
+        });
+    }
+}
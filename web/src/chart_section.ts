@@ -1,13 +1,27 @@
-import { CategorizedMessages } from "./types.js";
-import { calculateTotalSize } from "./utils.js";
+import { CategorizedMessages, SizeBucket } from "./types.js";
+import { calculateTotalSize, capitalize, formatSize } from "./utils.js";
+
+const BUCKET_COLORS = ['#4CAF50', '#FFC107', '#F44336', '#3b82f6', '#9C27B0', '#00BCD4', '#795548'];
 
 export default class ChartSectionComponent {
     private chart: any = null;
     private canvas: HTMLCanvasElement;
+    private buckets: SizeBucket[] = [];
+    private list: HTMLUListElement;
+    private editor: HTMLDivElement;
+    private editorRows: HTMLDivElement;
+    private editorError: HTMLParagraphElement;
+    private editButton: HTMLButtonElement;
+    public onSaveBuckets?: (buckets: SizeBucket[]) => Promise<string | null>;
 
     constructor() {
         this.canvas = document.createElement('canvas');
         this.canvas.height = 300;
+        this.list = document.createElement('ul');
+        this.editor = document.createElement('div');
+        this.editorRows = document.createElement('div');
+        this.editorError = document.createElement('p');
+        this.editButton = document.createElement('button');
     }
 
     public render(): HTMLDivElement {
@@ -23,27 +37,50 @@ export default class ChartSectionComponent {
         const classificationsContainer = document.createElement('div');
         classificationsContainer.className = 'bg-white rounded-lg shadow-lg p-6';
 
+        const titleRow = document.createElement('div');
+        titleRow.className = 'flex items-center justify-between mb-4';
+
         const title = document.createElement('h2');
-        title.className = 'text-xl font-semibold mb-4';
+        title.className = 'text-xl font-semibold';
         title.textContent = 'Size Classifications';
 
-        const list = document.createElement('ul');
-        list.className = 'list-disc pl-6';
-
-        const items = [
-            { label: 'Small:', desc: 'Less than 100KB' },
-            { label: 'Medium:', desc: 'Between 100KB and 1MB' },
-            { label: 'Large:', desc: '1MB or larger' }
-        ];
+        this.editButton.className = 'text-sm text-blue-600';
+        this.editButton.textContent = 'Edit';
+        this.editButton.addEventListener('click', () => {
+            this.showEditor(this.editor.style.display === 'none');
+        });
 
-        items.forEach(item => {
-            const li = document.createElement('li');
-            li.innerHTML = `<span class="font-medium">${item.label}</span> ${item.desc}`;
-            list.appendChild(li);
+        this.list.className = 'list-disc pl-6';
+
+        titleRow.appendChild(title);
+        titleRow.appendChild(this.editButton);
+        classificationsContainer.appendChild(titleRow);
+        classificationsContainer.appendChild(this.list);
+        classificationsContainer.appendChild(this.renderEditor());
+
+        const exportRow = document.createElement('p');
+        exportRow.className = 'text-sm text-gray-600 mt-4';
+        exportRow.appendChild(document.createTextNode('Export all scanned messages:'));
+        [
+            { format: 'csv', label: 'CSV' },
+            { format: 'json', label: 'JSON' },
+            { format: 'ndjson', label: 'NDJSON' }
+        ].forEach(item => {
+            const link = document.createElement('a');
+            link.className = 'text-blue-600 ml-2';
+            link.href = `/api/export?format=${item.format}`;
+            link.download = `gmail-messages.${item.format}`;
+            link.textContent = item.label;
+            exportRow.appendChild(link);
         });
 
-        classificationsContainer.appendChild(title);
-        classificationsContainer.appendChild(list);
+        const reportLink = document.createElement('a');
+        reportLink.className = 'text-blue-600 ml-4';
+        reportLink.href = '/report';
+        reportLink.target = '_blank';
+        reportLink.textContent = 'Storage report';
+        exportRow.appendChild(reportLink);
+        classificationsContainer.appendChild(exportRow);
 
         section.appendChild(chartContainer);
         section.appendChild(classificationsContainer);
@@ -51,6 +88,114 @@ export default class ChartSectionComponent {
         return section;
     }
 
+    private renderEditor(): HTMLDivElement {
+        this.editor.className = 'mt-4';
+        this.editor.style.display = 'none';
+        this.editorRows.className = 'space-y-2';
+        this.editorError.className = 'text-sm text-red-600 mt-2';
+
+        const buttons = document.createElement('div');
+        buttons.className = 'flex gap-3 mt-3';
+
+        const addButton = document.createElement('button');
+        addButton.className = 'text-sm text-blue-600';
+        addButton.textContent = 'Add bucket';
+        addButton.addEventListener('click', () => {
+            this.editorRows.appendChild(this.createEditorRow({ name: '', min_size: 0 }));
+        });
+
+        const saveButton = document.createElement('button');
+        saveButton.className = 'btn-primary';
+        saveButton.textContent = 'Save';
+        saveButton.addEventListener('click', async () => {
+            const error = await this.onSaveBuckets?.(this.readEditor());
+            this.editorError.textContent = error ?? '';
+            if (!error) {
+                this.showEditor(false);
+            }
+        });
+
+        buttons.appendChild(addButton);
+        buttons.appendChild(saveButton);
+        this.editor.appendChild(this.editorRows);
+        this.editor.appendChild(buttons);
+        this.editor.appendChild(this.editorError);
+
+        return this.editor;
+    }
+
+    private createEditorRow(bucket: SizeBucket): HTMLDivElement {
+        const row = document.createElement('div');
+        row.className = 'flex gap-3 flex-row items-center bucket-row';
+
+        const name = document.createElement('input');
+        name.className = 'input-field flex-1 bucket-name';
+        name.placeholder = 'Name';
+        name.value = bucket.name;
+
+        const minSize = document.createElement('input');
+        minSize.className = 'input-field w-24 bucket-min-size';
+        minSize.type = 'number';
+        minSize.min = '0';
+        minSize.value = (bucket.min_size / 1024).toString();
+
+        const unit = document.createElement('span');
+        unit.textContent = 'KB and up';
+
+        const removeButton = document.createElement('button');
+        removeButton.className = 'text-sm text-red-600';
+        removeButton.textContent = 'Remove';
+        removeButton.addEventListener('click', () => row.remove());
+
+        row.appendChild(name);
+        row.appendChild(minSize);
+        row.appendChild(unit);
+        row.appendChild(removeButton);
+
+        return row;
+    }
+
+    private readEditor(): SizeBucket[] {
+        return Array.from(this.editorRows.querySelectorAll('.bucket-row')).map(row => ({
+            name: (row.querySelector('.bucket-name') as HTMLInputElement).value.trim(),
+            min_size: Math.round((parseFloat((row.querySelector('.bucket-min-size') as HTMLInputElement).value) || 0) * 1024)
+        }));
+    }
+
+    private showEditor(show: boolean): void {
+        this.editor.style.display = show ? 'block' : 'none';
+        this.editButton.textContent = show ? 'Cancel' : 'Edit';
+        if (show) {
+            this.editorRows.innerHTML = '';
+            this.editorError.textContent = '';
+            this.buckets.forEach(bucket => {
+                this.editorRows.appendChild(this.createEditorRow(bucket));
+            });
+        }
+    }
+
+    public setBuckets(buckets: SizeBucket[]): void {
+        this.buckets = buckets;
+        this.list.innerHTML = '';
+        buckets.forEach((bucket, index) => {
+            const li = document.createElement('li');
+            li.innerHTML = `<span class="font-medium">${capitalize(bucket.name)}:</span> ${this.describeBucket(index)}`;
+            this.list.appendChild(li);
+        });
+    }
+
+    private describeBucket(index: number): string {
+        const min = this.buckets[index].min_size;
+        const next = this.buckets[index + 1];
+        if (!next) {
+            return `${formatSize(min)} or larger`;
+        }
+        if (min === 0) {
+            return `Less than ${formatSize(next.min_size)}`;
+        }
+        return `Between ${formatSize(min)} and ${formatSize(next.min_size)}`;
+    }
+
     public updateChart(categorizedMessages: CategorizedMessages): void {
         const ctx = this.canvas.getContext('2d');
         if (!ctx) return;
@@ -60,19 +205,15 @@ export default class ChartSectionComponent {
             this.chart.destroy();
         }
 
-        const data = [
-            calculateTotalSize(categorizedMessages.small) / 1024,
-            calculateTotalSize(categorizedMessages.medium) / 1024,
-            calculateTotalSize(categorizedMessages.large) / 1024
-        ];
+        const data = this.buckets.map(bucket => calculateTotalSize(categorizedMessages[bucket.name] ?? []) / 1024);
 
         this.chart = new (window as any).Chart(ctx, {
             type: 'pie',
             data: {
-                labels: ['Small (<100KB)', 'Medium (100KB-1MB)', 'Large (>1MB)'],
+                labels: this.buckets.map((bucket, index) => `${capitalize(bucket.name)} (${this.describeBucket(index)})`),
                 datasets: [{
                     data: data,
-                    backgroundColor: ['#4CAF50', '#FFC107', '#F44336']
+                    backgroundColor: this.buckets.map((_, index) => BUCKET_COLORS[index % BUCKET_COLORS.length])
                 }]
             },
             options: {
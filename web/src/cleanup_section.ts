@@ -0,0 +1,294 @@
+import { ArchiveReport, BulkSelection, BulkTrashReport, JournalEntry } from "./types.js";
+import { capitalize, formatSize } from "./utils.js";
+
+export default class CleanupSectionComponent {
+    private queryInput: HTMLInputElement;
+    private bucketSelect: HTMLSelectElement;
+    private senderInput: HTMLInputElement;
+    private groupSelect: HTMLSelectElement;
+    private categoryInput: HTMLInputElement;
+    private previewButton: HTMLButtonElement;
+    private trashButton: HTMLButtonElement;
+    private formatSelect: HTMLSelectElement;
+    private folderInput: HTMLInputElement;
+    private exportButton: HTMLButtonElement;
+    private summaryElement: HTMLParagraphElement;
+    private list: HTMLUListElement;
+    private journalList: HTMLUListElement;
+    private previewed: BulkSelection | null = null;
+    private previewedIds: string[] = [];
+    public onRun?: (selection: BulkSelection, dryRun: boolean, messageIds: string[]) => Promise<BulkTrashReport | string>;
+    public onUndo?: (entryId: number) => Promise<string>;
+    public onExport?: (selection: BulkSelection, format: string, folder: string) => Promise<ArchiveReport | string>;
+
+    constructor() {
+        this.queryInput = document.createElement('input');
+        this.bucketSelect = document.createElement('select');
+        this.senderInput = document.createElement('input');
+        this.groupSelect = document.createElement('select');
+        this.categoryInput = document.createElement('input');
+        this.previewButton = document.createElement('button');
+        this.trashButton = document.createElement('button');
+        this.formatSelect = document.createElement('select');
+        this.folderInput = document.createElement('input');
+        this.exportButton = document.createElement('button');
+        this.summaryElement = document.createElement('p');
+        this.list = document.createElement('ul');
+        this.journalList = document.createElement('ul');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        // Any change to the selection needs a new preview before trashing
+        [this.queryInput, this.senderInput, this.categoryInput].forEach(input => {
+            input.addEventListener('input', () => this.clearPreview());
+        });
+        [this.bucketSelect, this.groupSelect].forEach(select => {
+            select.addEventListener('change', () => this.clearPreview());
+        });
+
+        this.previewButton.addEventListener('click', async () => {
+            const selection = this.selection();
+            const result = await this.onRun?.(selection, true, []);
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.summaryElement.textContent = result;
+                return;
+            }
+            this.showReport(result);
+            this.previewed = selection;
+            this.previewedIds = result.messages.map(item => item.id);
+            this.trashButton.disabled = result.message_count === 0;
+        });
+
+        this.trashButton.addEventListener('click', async () => {
+            if (!this.previewed) return;
+            if (!confirm(`Move ${this.summaryElement.dataset.count} messages to the Trash? Gmail deletes them for good after 30 days.`)) {
+                return;
+            }
+            this.trashButton.disabled = true;
+            // Only the messages listed by the preview are trashed
+            const result = await this.onRun?.(this.previewed, false, this.previewedIds);
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.clearPreview();
+                this.summaryElement.textContent = result;
+                return;
+            }
+            this.list.innerHTML = '';
+            this.summaryElement.textContent = `Moved ${result.trashed} messages to the Trash` +
+                (result.failed > 0 ? `, ${result.failed} could not be moved` : '');
+            this.clearPreview();
+        });
+
+        this.exportButton.addEventListener('click', async () => {
+            this.exportButton.disabled = true;
+            this.summaryElement.textContent = 'Exporting...';
+            const result = await this.onExport?.(this.selection(), this.formatSelect.value, this.folderInput.value.trim());
+            this.exportButton.disabled = false;
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.summaryElement.textContent = result;
+                return;
+            }
+            this.summaryElement.textContent = `Exported ${result.archived.length} messages to ${result.folder}, listed in ${result.index}` +
+                (result.failed.length > 0 ? `. ${result.failed.length} could not be exported` : '');
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Bulk Cleanup';
+
+        const help = document.createElement('p');
+        help.className = 'text-sm text-gray-600 mb-4';
+        help.textContent = 'Pick the messages to move to the Trash. Every field filled in narrows the selection. Preview first to see exactly what would be moved, and export the messages to disk first to keep a copy.';
+
+        this.queryInput.className = 'input-field w-full p-2 mb-2';
+        this.queryInput.placeholder = 'Gmail search, e.g. older_than:2y larger:5M';
+
+        const bucketOption = document.createElement('option');
+        bucketOption.value = '';
+        bucketOption.textContent = 'Any size';
+        this.bucketSelect.appendChild(bucketOption);
+        this.bucketSelect.className = 'input-field p-2';
+
+        this.senderInput.className = 'input-field flex-1 p-2';
+        this.senderInput.placeholder = 'Sender address or domain';
+
+        [{ value: 'address', label: 'Address' }, { value: 'domain', label: 'Domain' }].forEach(group => {
+            const option = document.createElement('option');
+            option.value = group.value;
+            option.textContent = group.label;
+            this.groupSelect.appendChild(option);
+        });
+        this.groupSelect.className = 'input-field p-2';
+
+        this.categoryInput.className = 'input-field flex-1 p-2';
+        this.categoryInput.placeholder = 'Rule category';
+
+        const row = document.createElement('div');
+        row.className = 'flex gap-3 flex-row items-center mb-2';
+        row.appendChild(this.bucketSelect);
+        row.appendChild(this.senderInput);
+        row.appendChild(this.groupSelect);
+        row.appendChild(this.categoryInput);
+
+        const buttons = document.createElement('div');
+        buttons.className = 'flex gap-3 mt-2';
+
+        this.previewButton.className = 'btn-primary';
+        this.previewButton.textContent = 'Preview';
+
+        this.trashButton.className = 'btn-primary';
+        this.trashButton.textContent = 'Move to Trash';
+        this.trashButton.disabled = true;
+
+        buttons.appendChild(this.previewButton);
+        buttons.appendChild(this.trashButton);
+
+        [{ value: 'mbox', label: 'mbox file' }, { value: 'eml', label: '.eml files' }].forEach(format => {
+            const option = document.createElement('option');
+            option.value = format.value;
+            option.textContent = format.label;
+            this.formatSelect.appendChild(option);
+        });
+        this.formatSelect.className = 'input-field p-2';
+
+        this.folderInput.className = 'input-field flex-1 p-2';
+        this.folderInput.placeholder = 'Folder to export to, Downloads if empty';
+
+        this.exportButton.className = 'btn-primary';
+        this.exportButton.textContent = 'Export to Disk';
+
+        const exportRow = document.createElement('div');
+        exportRow.className = 'flex gap-3 flex-row items-center mt-2';
+        exportRow.appendChild(this.formatSelect);
+        exportRow.appendChild(this.folderInput);
+        exportRow.appendChild(this.exportButton);
+
+        this.summaryElement.className = 'mt-4 text-gray-600';
+        this.list.className = 'list-disc pl-6 mt-2';
+
+        section.appendChild(title);
+        section.appendChild(help);
+        section.appendChild(this.queryInput);
+        section.appendChild(row);
+        section.appendChild(buttons);
+        section.appendChild(exportRow);
+        section.appendChild(this.summaryElement);
+        section.appendChild(this.list);
+
+        const journalTitle = document.createElement('h3');
+        journalTitle.className = 'font-semibold mt-6 mb-2';
+        journalTitle.textContent = 'Recent Actions';
+
+        this.journalList.className = 'space-y-2';
+
+        section.appendChild(journalTitle);
+        section.appendChild(this.journalList);
+
+        return section;
+    }
+
+    public setBuckets(buckets: string[]): void {
+        while (this.bucketSelect.options.length > 1) {
+            this.bucketSelect.remove(1);
+        }
+        buckets.forEach(bucket => {
+            const option = document.createElement('option');
+            option.value = bucket;
+            option.textContent = capitalize(bucket);
+            this.bucketSelect.appendChild(option);
+        });
+    }
+
+    public setCanModify(modify: boolean): void {
+        this.trashButton.style.display = modify ? 'inline-block' : 'none';
+    }
+
+    public updateJournal(entries: JournalEntry[]): void {
+        this.journalList.innerHTML = '';
+        if (entries.length === 0) {
+            this.journalList.textContent = 'Nothing has been changed yet';
+            return;
+        }
+        entries.forEach(entry => {
+            const li = document.createElement('li');
+            li.className = 'flex items-center justify-between text-sm';
+
+            const description = document.createElement('span');
+            const count = entry.changes.length + entry.thread_ids.length;
+            const actions: { [action: string]: string } = {
+                trash: 'Moved to the Trash',
+                bulk_trash: 'Moved to the Trash',
+                archive: 'Archived',
+                label: 'Labelled',
+                strip_attachments: 'Removed attachments from'
+            };
+            const what = actions[entry.action] ?? entry.action;
+            description.textContent = `${new Date(entry.performed_at).toLocaleString()}: ${what} ${count} ${entry.thread_ids.length > 0 ? 'items' : 'messages'}`;
+            li.appendChild(description);
+
+            if (entry.undone) {
+                const undone = document.createElement('span');
+                undone.className = 'text-gray-500';
+                undone.textContent = 'Undone';
+                li.appendChild(undone);
+            } else {
+                const undoButton = document.createElement('button');
+                undoButton.className = 'text-blue-600';
+                undoButton.textContent = 'Undo';
+                undoButton.addEventListener('click', async () => {
+                    undoButton.disabled = true;
+                    const result = await this.onUndo?.(entry.id);
+                    if (result) {
+                        undoButton.textContent = result;
+                    }
+                });
+                li.appendChild(undoButton);
+            }
+            this.journalList.appendChild(li);
+        });
+    }
+
+    private selection(): BulkSelection {
+        const selection: BulkSelection = {};
+        const query = this.queryInput.value.trim();
+        const sender = this.senderInput.value.trim().toLowerCase();
+        const category = this.categoryInput.value.trim();
+        if (query) selection.query = query;
+        if (this.bucketSelect.value) selection.bucket = this.bucketSelect.value;
+        if (sender) {
+            selection.sender = sender;
+            selection.group = this.groupSelect.value;
+        }
+        if (category) selection.category = category;
+        return selection;
+    }
+
+    private clearPreview(): void {
+        this.previewed = null;
+        this.previewedIds = [];
+        this.trashButton.disabled = true;
+    }
+
+    private showReport(report: BulkTrashReport): void {
+        this.summaryElement.dataset.count = report.message_count.toString();
+        this.summaryElement.textContent = `${report.message_count} messages, ${formatSize(report.total_size)} would be moved to the Trash`;
+        this.list.innerHTML = '';
+        report.messages.forEach(item => {
+            const li = document.createElement('li');
+            const link = document.createElement('a');
+            link.className = 'text-gray-900 hover:text-blue-600 transition-colors';
+            link.href = `https://mail.google.com/mail/u/0/#all/${item.thread_id}`;
+            link.target = '_blank';
+            link.textContent = `${item.title} (${formatSize(item.size)})`;
+            li.appendChild(link);
+            this.list.appendChild(li);
+        });
+    }
+}
@@ -0,0 +1,105 @@
+import { DuplicateReport, DuplicateGroup } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class DuplicateSectionComponent {
+    private summaryElement: HTMLParagraphElement;
+    private verifyButton: HTMLButtonElement;
+    private resultsContainer: HTMLDivElement;
+    public onVerify?: () => void;
+
+    constructor() {
+        this.summaryElement = document.createElement('p');
+        this.verifyButton = document.createElement('button');
+        this.resultsContainer = document.createElement('div');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        this.verifyButton.addEventListener('click', () => {
+            this.onVerify?.();
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const headerRow = document.createElement('div');
+        headerRow.className = 'flex items-center justify-between mb-4';
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold';
+        title.textContent = 'Duplicate Attachments';
+
+        this.verifyButton.className = 'btn-primary';
+        this.verifyButton.textContent = 'Compare contents';
+
+        this.summaryElement.className = 'mb-4 text-gray-600';
+        this.resultsContainer.className = 'space-y-3 pr-2';
+
+        headerRow.appendChild(title);
+        headerRow.appendChild(this.verifyButton);
+        section.appendChild(headerRow);
+        section.appendChild(this.summaryElement);
+        section.appendChild(this.resultsContainer);
+
+        return section;
+    }
+
+    public setLoading(loading: boolean): void {
+        this.verifyButton.disabled = loading;
+        this.verifyButton.textContent = loading ? 'Comparing...' : 'Compare contents';
+    }
+
+    public updateReport(report: DuplicateReport): void {
+        this.summaryElement.textContent = `Redundant copies take up ${formatSize(report.redundant_size)}`;
+        this.resultsContainer.innerHTML = '';
+        report.groups.forEach(group => {
+            this.resultsContainer.appendChild(this.createGroupElement(group));
+        });
+    }
+
+    private createGroupElement(group: DuplicateGroup): HTMLDivElement {
+        const div = document.createElement('div');
+        div.className = 'message-card';
+
+        const flexContainer = document.createElement('div');
+        flexContainer.className = 'flex items-center justify-between';
+
+        const contentDiv = document.createElement('div');
+        contentDiv.className = 'flex-1 min-w-0';
+
+        const name = document.createElement('p');
+        name.className = 'font-semibold truncate text-lg';
+        name.textContent = group.filename;
+
+        const details = document.createElement('p');
+        details.className = 'text-sm text-gray-600';
+        details.textContent = `${group.copies.length} copies of ${formatSize(group.size)}${group.verified ? ', identical contents' : ''}`;
+
+        const copiesList = document.createElement('ul');
+        copiesList.className = 'list-disc pl-6 mt-2';
+        group.copies.forEach(copy => {
+            const li = document.createElement('li');
+            const link = document.createElement('a');
+            link.className = 'text-gray-900 hover:text-blue-600 transition-colors';
+            link.href = `https://mail.google.com/mail/u/0/#all/${copy.thread_id}`;
+            link.target = '_blank';
+            link.textContent = copy.subject;
+            li.appendChild(link);
+            copiesList.appendChild(li);
+        });
+
+        const sizeSpan = document.createElement('span');
+        sizeSpan.className = 'size-badge';
+        sizeSpan.textContent = formatSize(group.redundant_size);
+
+        contentDiv.appendChild(name);
+        contentDiv.appendChild(details);
+        contentDiv.appendChild(copiesList);
+        flexContainer.appendChild(contentDiv);
+        flexContainer.appendChild(sizeSpan);
+        div.appendChild(flexContainer);
+
+        return div;
+    }
+}
@@ -0,0 +1,170 @@
+import { FilterPreview, FilterRequest } from "./types.js";
+
+export default class FilterSectionComponent {
+    private senderInput: HTMLInputElement;
+    private listInput: HTMLInputElement;
+    private queryInput: HTMLInputElement;
+    private actionSelect: HTMLSelectElement;
+    private labelInput: HTMLInputElement;
+    private previewButton: HTMLButtonElement;
+    private createButton: HTMLButtonElement;
+    private enableLink: HTMLAnchorElement;
+    private summaryElement: HTMLParagraphElement;
+    private previewed: FilterRequest | null = null;
+    public onRun?: (request: FilterRequest, dryRun: boolean) => Promise<FilterPreview | string>;
+
+    constructor() {
+        this.senderInput = document.createElement('input');
+        this.listInput = document.createElement('input');
+        this.queryInput = document.createElement('input');
+        this.actionSelect = document.createElement('select');
+        this.labelInput = document.createElement('input');
+        this.previewButton = document.createElement('button');
+        this.createButton = document.createElement('button');
+        this.enableLink = document.createElement('a');
+        this.summaryElement = document.createElement('p');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        // Any change to the filter needs a new preview before creating it
+        [this.senderInput, this.listInput, this.queryInput, this.labelInput].forEach(input => {
+            input.addEventListener('input', () => this.clearPreview());
+        });
+        this.actionSelect.addEventListener('change', () => {
+            this.labelInput.style.display = this.actionSelect.value === 'label' ? 'block' : 'none';
+            this.clearPreview();
+        });
+
+        this.previewButton.addEventListener('click', async () => {
+            const request = this.request();
+            const result = await this.onRun?.(request, true);
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.summaryElement.textContent = result;
+                return;
+            }
+            const matching = result.matching_estimate === null
+                ? ''
+                : ` About ${result.matching_estimate} existing messages match.`;
+            this.summaryElement.textContent = `Matches: ${result.search}. Action: ${result.description}.${matching}`;
+            this.previewed = request;
+            this.createButton.disabled = false;
+        });
+
+        this.createButton.addEventListener('click', async () => {
+            if (!this.previewed) return;
+            this.createButton.disabled = true;
+            const result = await this.onRun?.(this.previewed, false);
+            if (!result) return;
+            if (typeof result === 'string') {
+                this.summaryElement.textContent = result;
+                return;
+            }
+            this.summaryElement.textContent = `Created a filter for ${result.search}. New mail will be handled automatically.`;
+            this.previewed = null;
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Filters';
+
+        const help = document.createElement('p');
+        help.className = 'text-sm text-gray-600 mb-4';
+        help.textContent = 'Keep the mailbox clean by letting Gmail handle future mail from a sender, mailing list or search. Preview first to see the filter that would be created.';
+
+        this.senderInput.className = 'input-field flex-1 p-2';
+        this.senderInput.placeholder = 'Sender address or domain';
+
+        this.listInput.className = 'input-field flex-1 p-2';
+        this.listInput.placeholder = 'List ID, e.g. news.example.com';
+
+        this.queryInput.className = 'input-field w-full p-2 mb-2';
+        this.queryInput.placeholder = 'Gmail search, e.g. subject:receipt';
+
+        [
+            { value: 'archive', label: 'Archive' },
+            { value: 'label', label: 'Apply label' },
+            { value: 'delete', label: 'Delete' }
+        ].forEach(action => {
+            const option = document.createElement('option');
+            option.value = action.value;
+            option.textContent = action.label;
+            this.actionSelect.appendChild(option);
+        });
+        this.actionSelect.className = 'input-field p-2';
+
+        this.labelInput.className = 'input-field flex-1 p-2';
+        this.labelInput.placeholder = 'Label name';
+        this.labelInput.style.display = 'none';
+
+        const row = document.createElement('div');
+        row.className = 'flex gap-3 flex-row items-center mb-2';
+        row.appendChild(this.senderInput);
+        row.appendChild(this.listInput);
+
+        const actionRow = document.createElement('div');
+        actionRow.className = 'flex gap-3 flex-row items-center mb-2';
+        actionRow.appendChild(this.actionSelect);
+        actionRow.appendChild(this.labelInput);
+
+        const buttons = document.createElement('div');
+        buttons.className = 'flex gap-3 mt-2';
+
+        this.previewButton.className = 'btn-primary';
+        this.previewButton.textContent = 'Preview';
+
+        this.createButton.className = 'btn-primary';
+        this.createButton.textContent = 'Create Filter';
+        this.createButton.disabled = true;
+
+        // Filters need a sign in with permission to change Gmail settings
+        this.enableLink.className = 'text-blue-600 hover:underline self-center';
+        this.enableLink.href = '/login?mode=filters';
+        this.enableLink.textContent = 'Allow filters';
+        this.enableLink.title = 'Sign in again allowing the app to create Gmail filters';
+
+        buttons.appendChild(this.previewButton);
+        buttons.appendChild(this.createButton);
+        buttons.appendChild(this.enableLink);
+
+        this.summaryElement.className = 'mt-4 text-gray-600';
+
+        section.appendChild(title);
+        section.appendChild(help);
+        section.appendChild(row);
+        section.appendChild(this.queryInput);
+        section.appendChild(actionRow);
+        section.appendChild(buttons);
+        section.appendChild(this.summaryElement);
+
+        return section;
+    }
+
+    public setCanModify(filters: boolean): void {
+        this.createButton.style.display = filters ? 'inline-block' : 'none';
+        this.enableLink.style.display = filters ? 'none' : 'inline';
+    }
+
+    private request(): FilterRequest {
+        const request: FilterRequest = { action: this.actionSelect.value };
+        const sender = this.senderInput.value.trim().toLowerCase();
+        const listId = this.listInput.value.trim().toLowerCase();
+        const query = this.queryInput.value.trim();
+        const label = this.labelInput.value.trim();
+        if (sender) request.sender = sender;
+        if (listId) request.list_id = listId;
+        if (query) request.query = query;
+        if (this.actionSelect.value === 'label' && label) request.label = label;
+        return request;
+    }
+
+    private clearPreview(): void {
+        this.previewed = null;
+        this.createButton.disabled = true;
+    }
+}
@@ -4,6 +4,7 @@ export default class HeaderComponent {
     private titleContainer: HTMLElement | null = null;
     private profileContainer: HTMLElement;
     private aboutLink: HTMLElement | null = null;
+    private cleanupLink: HTMLAnchorElement;
     private profileData: ProfileData | null = null;
     public onShowAbout?: () => void;
     public onShowHome?: () => void;
@@ -11,6 +12,7 @@ export default class HeaderComponent {
     constructor() {
         this.profileContainer = document.createElement('div');
         this.profileContainer.className = 'profile-container';
+        this.cleanupLink = document.createElement('a');
     }
 
     render(): HTMLElement {
@@ -51,10 +53,18 @@ export default class HeaderComponent {
         this.aboutLink.className = 'about-link';
         this.aboutLink.style.cursor = 'pointer';
         
+        // Cleanup needs a sign in with permission to modify Gmail
+        this.cleanupLink.textContent = 'Enable cleanup';
+        this.cleanupLink.className = 'about-link';
+        this.cleanupLink.href = '/login?mode=modify';
+        this.cleanupLink.title = 'Sign in again allowing the app to move mail to the Trash';
+        this.cleanupLink.style.display = 'none';
+
         // Right section with profile
         const rightSection = document.createElement('div');
         rightSection.className = 'header-right';
         rightSection.appendChild(this.profileContainer);
+        rightSection.appendChild(this.cleanupLink);
         rightSection.appendChild(this.aboutLink);
         
         header.appendChild(leftSection);
@@ -77,6 +87,10 @@ export default class HeaderComponent {
         });
     }
 
+    setCanModify(modify: boolean) {
+        this.cleanupLink.style.display = modify || !this.profileData ? 'none' : 'inline';
+    }
+
     updateProfile(profileData: ProfileData | null) {
         this.profileData = profileData;
         
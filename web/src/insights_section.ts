@@ -0,0 +1,61 @@
+export default class InsightsSectionComponent {
+    private tabHeader: HTMLDivElement;
+    private tabContentContainer: HTMLDivElement;
+    private tabContents: { [key: string]: HTMLDivElement } = {};
+    private tabHeaders: { [key: string]: HTMLDivElement } = {};
+    public onTabChange?: (key: string) => void;
+
+    constructor() {
+        this.tabHeader = document.createElement('div');
+        this.tabContentContainer = document.createElement('div');
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+        section.className = 'flex flex-col mt-8';
+
+        this.tabHeader.className = 'tab-header flex';
+
+        section.appendChild(this.tabHeader);
+        section.appendChild(this.tabContentContainer);
+
+        return section;
+    }
+
+    public addTab(key: string, title: string, content: HTMLElement): void {
+        const header = document.createElement('div');
+        header.className = 'tab-button flex-1 text-center';
+        header.id = `${key}-insights-heading`;
+        header.textContent = title;
+        header.addEventListener('click', () => {
+            this.switchToTab(key);
+            this.onTabChange?.(key);
+        });
+
+        const tabContent = document.createElement('div');
+        tabContent.className = 'bg-white rounded-lg shadow-lg p-6 hidden';
+        tabContent.id = `${key}-insights-tab`;
+        tabContent.appendChild(content);
+
+        this.tabHeaders[key] = header;
+        this.tabContents[key] = tabContent;
+        this.tabHeader.appendChild(header);
+        this.tabContentContainer.appendChild(tabContent);
+
+        if (Object.keys(this.tabContents).length === 1) {
+            this.switchToTab(key);
+        }
+    }
+
+    public switchToTab(key: string): void {
+        Object.entries(this.tabContents).forEach(([tabKey, element]) => {
+            if (tabKey === key) {
+                element.classList.remove('hidden');
+                this.tabHeaders[tabKey].classList.add('active');
+            } else {
+                element.classList.add('hidden');
+                this.tabHeaders[tabKey].classList.remove('active');
+            }
+        });
+    }
+}
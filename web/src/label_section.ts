@@ -0,0 +1,76 @@
+import { LabelTotal } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class LabelSectionComponent {
+    private chart: any = null;
+    private canvas: HTMLCanvasElement;
+    private list: HTMLUListElement;
+
+    constructor() {
+        this.canvas = document.createElement('canvas');
+        this.canvas.height = 300;
+        this.list = document.createElement('ul');
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+        section.className = 'grid grid-cols-1 md:grid-cols-2 gap-6';
+
+        const chartContainer = document.createElement('div');
+        chartContainer.appendChild(this.canvas);
+
+        const listContainer = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Storage by Label';
+
+        this.list.className = 'list-disc pl-6';
+
+        listContainer.appendChild(title);
+        listContainer.appendChild(this.list);
+
+        section.appendChild(chartContainer);
+        section.appendChild(listContainer);
+
+        return section;
+    }
+
+    public updateLabels(labels: LabelTotal[]): void {
+        this.list.innerHTML = '';
+        labels.forEach(label => {
+            const li = document.createElement('li');
+            li.innerHTML = `<span class="font-medium">${label.name}:</span> ${label.message_count} messages, ${formatSize(label.total_size)}`;
+            this.list.appendChild(li);
+        });
+
+        const ctx = this.canvas.getContext('2d');
+        if (!ctx) return;
+
+        if (this.chart) {
+            this.chart.destroy();
+        }
+
+        const topLabels = labels.slice(0, 10);
+        this.chart = new (window as any).Chart(ctx, {
+            type: 'bar',
+            data: {
+                labels: topLabels.map(label => label.name),
+                datasets: [{
+                    label: 'Size (KB)',
+                    data: topLabels.map(label => label.total_size / 1024),
+                    backgroundColor: '#667eea'
+                }]
+            },
+            options: {
+                indexAxis: 'y',
+                responsive: true,
+                plugins: {
+                    legend: {
+                        display: false
+                    }
+                }
+            }
+        });
+    }
+}
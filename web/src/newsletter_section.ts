@@ -0,0 +1,88 @@
+import { ListTotal } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class NewsletterSectionComponent {
+    private summaryElement: HTMLParagraphElement;
+    private resultsContainer: HTMLDivElement;
+    public onUnsubscribe?: (list: ListTotal) => Promise<string>;
+
+    constructor() {
+        this.summaryElement = document.createElement('p');
+        this.resultsContainer = document.createElement('div');
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Newsletters and Bulk Mail';
+
+        this.summaryElement.className = 'mb-4 text-gray-600';
+        this.resultsContainer.className = 'space-y-3 pr-2';
+
+        section.appendChild(title);
+        section.appendChild(this.summaryElement);
+        section.appendChild(this.resultsContainer);
+
+        return section;
+    }
+
+    public updateLists(lists: ListTotal[]): void {
+        const totalSize = lists.reduce((sum, list) => sum + list.total_size, 0);
+        const totalCount = lists.reduce((sum, list) => sum + list.message_count, 0);
+        this.summaryElement.textContent = `${totalCount} messages from ${lists.length} lists and bulk senders take up ${formatSize(totalSize)}`;
+
+        this.resultsContainer.innerHTML = '';
+        lists.forEach(list => {
+            this.resultsContainer.appendChild(this.createListElement(list));
+        });
+    }
+
+    private createListElement(list: ListTotal): HTMLDivElement {
+        const div = document.createElement('div');
+        div.className = 'message-card';
+
+        const flexContainer = document.createElement('div');
+        flexContainer.className = 'flex items-center justify-between';
+
+        const contentDiv = document.createElement('div');
+        contentDiv.className = 'flex-1 min-w-0';
+
+        const name = document.createElement('p');
+        name.className = 'font-semibold truncate text-lg';
+        name.textContent = list.name || list.list_id;
+
+        const details = document.createElement('p');
+        details.className = 'text-sm text-gray-600 truncate';
+        const lastReceived = new Date(list.latest_date).toLocaleDateString();
+        details.textContent = `${list.sender} · ${list.message_count} messages · last received ${lastReceived}`;
+
+        const sizeSpan = document.createElement('span');
+        sizeSpan.className = 'size-badge';
+        sizeSpan.textContent = formatSize(list.total_size);
+
+        const status = document.createElement('p');
+        status.className = 'text-sm text-gray-600 mt-2';
+
+        contentDiv.appendChild(name);
+        contentDiv.appendChild(details);
+        flexContainer.appendChild(contentDiv);
+        if (list.unsubscribe) {
+            const unsubscribeButton = document.createElement('button');
+            unsubscribeButton.className = 'text-sm text-red-600 ml-4';
+            unsubscribeButton.textContent = 'Unsubscribe';
+            unsubscribeButton.addEventListener('click', async () => {
+                unsubscribeButton.disabled = true;
+                status.textContent = await this.onUnsubscribe?.(list) ?? '';
+                unsubscribeButton.disabled = false;
+            });
+            flexContainer.appendChild(unsubscribeButton);
+        }
+        flexContainer.appendChild(sizeSpan);
+        div.appendChild(flexContainer);
+        div.appendChild(status);
+
+        return div;
+    }
+}
@@ -0,0 +1,159 @@
+import { CategoryTotal, MessageItem, Rule } from "./types.js";
+import { formatSize } from "./utils.js";
+
+const EXAMPLE_RULES: Rule[] = [
+    {
+        name: 'Old LinkedIn mail',
+        category: 'Old social',
+        conditions: [
+            { field: 'sender', pattern: '*@linkedin.com' },
+            { field: 'older_than', days: 365 }
+        ]
+    },
+    {
+        name: 'Videos',
+        category: 'Videos',
+        conditions: [
+            { field: 'attachment_kind', kind: 'video' }
+        ]
+    }
+];
+
+export default class RuleSectionComponent {
+    private resultsContainer: HTMLDivElement;
+    private editor: HTMLTextAreaElement;
+    private editorError: HTMLParagraphElement;
+    private saveButton: HTMLButtonElement;
+    public onSaveRules?: (rules: Rule[]) => Promise<string | null>;
+    public onDrillDown?: (category: string) => Promise<MessageItem[]>;
+
+    constructor() {
+        this.resultsContainer = document.createElement('div');
+        this.editor = document.createElement('textarea');
+        this.editorError = document.createElement('p');
+        this.saveButton = document.createElement('button');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        this.saveButton.addEventListener('click', async () => {
+            let rules: Rule[];
+            try {
+                rules = JSON.parse(this.editor.value);
+            } catch (error) {
+                this.editorError.textContent = `Rules are not valid JSON: ${error}`;
+                return;
+            }
+            const error = await this.onSaveRules?.(rules);
+            this.editorError.textContent = error ?? '';
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Rule Categories';
+
+        this.resultsContainer.className = 'space-y-3 pr-2 mb-6';
+
+        const editorTitle = document.createElement('h3');
+        editorTitle.className = 'font-semibold mb-2';
+        editorTitle.textContent = 'Rules';
+
+        const help = document.createElement('p');
+        help.className = 'text-sm text-gray-600 mb-2';
+        help.textContent = 'Rules are checked in order and a message goes in the category of the first rule all of whose conditions it meets. Condition fields: sender, label, older_than, newer_than, larger_than, smaller_than, attachment_kind, subject.';
+
+        this.editor.className = 'input-field w-full font-mono text-sm p-2';
+        this.editor.rows = 12;
+
+        this.saveButton.className = 'btn-primary mt-3';
+        this.saveButton.textContent = 'Save rules';
+
+        this.editorError.className = 'text-sm text-red-600 mt-2';
+
+        section.appendChild(title);
+        section.appendChild(this.resultsContainer);
+        section.appendChild(editorTitle);
+        section.appendChild(help);
+        section.appendChild(this.editor);
+        section.appendChild(this.saveButton);
+        section.appendChild(this.editorError);
+
+        return section;
+    }
+
+    public setRules(rules: Rule[]): void {
+        // Start users off with an example of each kind of rule
+        this.editor.value = JSON.stringify(rules.length > 0 ? rules : EXAMPLE_RULES, null, 2);
+        this.editorError.textContent = '';
+    }
+
+    public updateCategories(categories: CategoryTotal[]): void {
+        this.resultsContainer.innerHTML = '';
+        if (categories.length === 0) {
+            this.resultsContainer.textContent = 'No scanned messages match the rules';
+            return;
+        }
+        categories.forEach(category => {
+            this.resultsContainer.appendChild(this.createCategoryElement(category));
+        });
+    }
+
+    private createCategoryElement(category: CategoryTotal): HTMLDivElement {
+        const div = document.createElement('div');
+        div.className = 'message-card';
+
+        const flexContainer = document.createElement('div');
+        flexContainer.className = 'flex items-center justify-between cursor-pointer';
+
+        const contentDiv = document.createElement('div');
+        contentDiv.className = 'flex-1 min-w-0';
+
+        const name = document.createElement('p');
+        name.className = 'font-semibold truncate text-lg';
+        name.textContent = category.category;
+
+        const count = document.createElement('p');
+        count.className = 'text-sm text-gray-600';
+        count.textContent = `${category.message_count} messages`;
+
+        const sizeSpan = document.createElement('span');
+        sizeSpan.className = 'size-badge';
+        sizeSpan.textContent = formatSize(category.total_size);
+
+        const messagesList = document.createElement('ul');
+        messagesList.className = 'list-disc pl-6 mt-2 hidden';
+
+        flexContainer.addEventListener('click', async () => {
+            if (!messagesList.classList.contains('hidden')) {
+                messagesList.classList.add('hidden');
+                return;
+            }
+            const messages = await this.onDrillDown?.(category.category) ?? [];
+            messagesList.innerHTML = '';
+            messages.forEach(item => {
+                const li = document.createElement('li');
+                const link = document.createElement('a');
+                link.className = 'text-gray-900 hover:text-blue-600 transition-colors';
+                link.href = `https://mail.google.com/mail/u/0/#all/${item.thread_id}`;
+                link.target = '_blank';
+                link.textContent = `${item.title} (${formatSize(item.size)})`;
+                li.appendChild(link);
+                messagesList.appendChild(li);
+            });
+            messagesList.classList.remove('hidden');
+        });
+
+        contentDiv.appendChild(name);
+        contentDiv.appendChild(count);
+        flexContainer.appendChild(contentDiv);
+        flexContainer.appendChild(sizeSpan);
+        div.appendChild(flexContainer);
+        div.appendChild(messagesList);
+
+        return div;
+    }
+}
@@ -1,13 +1,18 @@
 export default class SearchSectionComponent {
     private input: HTMLInputElement;
+    private strategySelect: HTMLSelectElement;
     private button: HTMLButtonElement;
+    private syncButton: HTMLButtonElement;
     private loadingElement: HTMLSpanElement;
     private statusElement: HTMLDivElement;
-    public onSearch?: (maxMessages: number) => void;
+    public onSearch?: (maxMessages: number, strategy: string) => void;
+    public onSync?: () => void;
 
     constructor() {
         this.input = document.createElement('input');
+        this.strategySelect = document.createElement('select');
         this.button = document.createElement('button');
+        this.syncButton = document.createElement('button');
         this.loadingElement = document.createElement('span');
         this.statusElement = document.createElement('div');
         this.setupElements();
@@ -22,9 +27,24 @@ export default class SearchSectionComponent {
         this.input.value = '20';
         this.input.className = 'input-field flex-1';
 
+        const strategies = [
+            { value: 'newest', label: 'Newest first' },
+            { value: 'largest', label: 'Largest first' }
+        ];
+        strategies.forEach(strategy => {
+            const option = document.createElement('option');
+            option.value = strategy.value;
+            option.textContent = strategy.label;
+            this.strategySelect.appendChild(option);
+        });
+        this.strategySelect.className = 'input-field';
+
         this.button.textContent = 'Analyze first 20 emails';
         this.button.className = 'btn-primary';
 
+        this.syncButton.textContent = 'Sync whole mailbox';
+        this.syncButton.className = 'btn-primary mt-2';
+
         this.loadingElement.className = 'loading inline-flex items-center gap-2';
         this.loadingElement.innerHTML = `
             <svg class="animate-spin h-4 w-4" xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24">
@@ -49,7 +69,11 @@ export default class SearchSectionComponent {
             const clampedValue = Math.min(Math.max(maxMessages, 1), 50);
             this.input.value = clampedValue.toString();
             this.button.textContent = `Analyze next ${clampedValue} emails`;
-            this.onSearch?.(clampedValue);
+            this.onSearch?.(clampedValue, this.strategySelect.value);
+        });
+
+        this.syncButton.addEventListener('click', () => {
+            this.onSync?.();
         });
     }
 
@@ -73,11 +97,13 @@ export default class SearchSectionComponent {
 
         inputContainer.appendChild(label);
         inputContainer.appendChild(this.input);
+        inputContainer.appendChild(this.strategySelect);
         loadingContainer.appendChild(this.loadingElement);
         loadingContainer.appendChild(this.statusElement);
 
         container.appendChild(inputContainer);
         container.appendChild(this.button);
+        container.appendChild(this.syncButton);
         container.appendChild(loadingContainer);
         section.appendChild(container);
 
@@ -87,7 +113,9 @@ export default class SearchSectionComponent {
     public setLoading(loading: boolean): void {
         this.loadingElement.style.display = loading ? 'inline-flex' : 'none';
         this.button.disabled = loading;
+        this.syncButton.disabled = loading;
         this.input.disabled = loading;
+        this.strategySelect.disabled = loading;
     }
 
     public updateAnalyzedCount(count: number): void {
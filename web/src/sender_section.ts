@@ -0,0 +1,119 @@
+import { MessageItem, SenderTotal } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class SenderSectionComponent {
+    private groupSelect: HTMLSelectElement;
+    private resultsContainer: HTMLDivElement;
+    public onGroupChange?: (group: string) => void;
+    public onDrillDown?: (sender: string, group: string) => Promise<MessageItem[]>;
+
+    constructor() {
+        this.groupSelect = document.createElement('select');
+        this.resultsContainer = document.createElement('div');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        this.groupSelect.addEventListener('change', () => {
+            this.onGroupChange?.(this.groupSelect.value);
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const headerRow = document.createElement('div');
+        headerRow.className = 'flex items-center justify-between mb-4';
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold';
+        title.textContent = 'Top Senders';
+
+        const groups = [
+            { value: 'address', label: 'By address' },
+            { value: 'domain', label: 'By domain' }
+        ];
+        groups.forEach(group => {
+            const option = document.createElement('option');
+            option.value = group.value;
+            option.textContent = group.label;
+            this.groupSelect.appendChild(option);
+        });
+        this.groupSelect.className = 'input-field';
+
+        this.resultsContainer.className = 'space-y-3 pr-2';
+
+        headerRow.appendChild(title);
+        headerRow.appendChild(this.groupSelect);
+        section.appendChild(headerRow);
+        section.appendChild(this.resultsContainer);
+
+        return section;
+    }
+
+    public get group(): string {
+        return this.groupSelect.value;
+    }
+
+    public updateSenders(senders: SenderTotal[]): void {
+        this.resultsContainer.innerHTML = '';
+        senders.forEach(sender => {
+            this.resultsContainer.appendChild(this.createSenderElement(sender));
+        });
+    }
+
+    private createSenderElement(sender: SenderTotal): HTMLDivElement {
+        const div = document.createElement('div');
+        div.className = 'message-card';
+
+        const flexContainer = document.createElement('div');
+        flexContainer.className = 'flex items-center justify-between cursor-pointer';
+
+        const contentDiv = document.createElement('div');
+        contentDiv.className = 'flex-1 min-w-0';
+
+        const name = document.createElement('p');
+        name.className = 'font-semibold truncate text-lg';
+        name.textContent = sender.sender || '(unknown sender)';
+
+        const count = document.createElement('p');
+        count.className = 'text-sm text-gray-600';
+        count.textContent = `${sender.message_count} messages`;
+
+        const sizeSpan = document.createElement('span');
+        sizeSpan.className = 'size-badge';
+        sizeSpan.textContent = formatSize(sender.total_size);
+
+        const messagesList = document.createElement('ul');
+        messagesList.className = 'list-disc pl-6 mt-2 hidden';
+
+        flexContainer.addEventListener('click', async () => {
+            if (!messagesList.classList.contains('hidden')) {
+                messagesList.classList.add('hidden');
+                return;
+            }
+            const messages = await this.onDrillDown?.(sender.sender, this.group) ?? [];
+            messagesList.innerHTML = '';
+            messages.forEach(item => {
+                const li = document.createElement('li');
+                const link = document.createElement('a');
+                link.className = 'text-gray-900 hover:text-blue-600 transition-colors';
+                link.href = `https://mail.google.com/mail/u/0/#all/${item.thread_id}`;
+                link.target = '_blank';
+                link.textContent = `${item.title} (${formatSize(item.size)})`;
+                li.appendChild(link);
+                messagesList.appendChild(li);
+            });
+            messagesList.classList.remove('hidden');
+        });
+
+        contentDiv.appendChild(name);
+        contentDiv.appendChild(count);
+        flexContainer.appendChild(contentDiv);
+        flexContainer.appendChild(sizeSpan);
+        div.appendChild(flexContainer);
+        div.appendChild(messagesList);
+
+        return div;
+    }
+}
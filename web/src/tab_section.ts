@@ -1,13 +1,26 @@
 import { MessageItem, CategorizedMessages } from "./types.js";
-import { calculateTotalSize, formatSize } from "./utils.js";
+import { calculateTotalSize, capitalize, formatSize } from "./utils.js";
 
 export default class TabsSectionComponent {
     private tabContents: { [key: string]: HTMLDivElement } = {};
     private tabHeaders: { [key: string]: HTMLDivElement } = {};
     private resultContainers: { [key: string]: HTMLDivElement } = {};
+    private tabHeader: HTMLDivElement;
+    private tabContentContainer: HTMLDivElement;
+    private countElements: { [key: string]: HTMLParagraphElement } = {};
+    private sizeElements: { [key: string]: HTMLParagraphElement } = {};
+    private toolbars: HTMLDivElement[] = [];
+    private actionButtons: HTMLButtonElement[] = [];
+    private selected: Set<string> = new Set();
+    private canModify: boolean = false;
     public onTabChange?: (category: string) => void;
+    public onTrash?: (messageIds: string[]) => Promise<void>;
+    public onArchive?: (messageIds: string[]) => Promise<void>;
+    public onLabel?: (messageIds: string[], label: string) => Promise<void>;
 
     constructor() {
+        this.tabHeader = document.createElement('div');
+        this.tabContentContainer = document.createElement('div');
         this.setupEventListeners();
     }
 
@@ -20,63 +33,139 @@ export default class TabsSectionComponent {
         section.className = 'flex flex-col';
 
         // Tab headers
-        const tabHeader = document.createElement('div');
-        tabHeader.className = 'tab-header flex';
+        this.tabHeader.className = 'tab-header flex';
+
+        section.appendChild(this.tabHeader);
+        section.appendChild(this.tabContentContainer);
+
+        return section;
+    }
+
+    public setBuckets(categories: string[]): void {
+        this.tabHeader.innerHTML = '';
+        this.tabContentContainer.innerHTML = '';
+        this.tabContents = {};
+        this.tabHeaders = {};
+        this.resultContainers = {};
+        this.countElements = {};
+        this.sizeElements = {};
+        this.toolbars = [];
+        this.actionButtons = [];
 
-        const categories = ['small', 'medium', 'large'];
         categories.forEach(category => {
             const header = document.createElement('div');
             header.className = 'tab-button flex-1 text-center';
-            header.id = `${category}-heading`;
-            header.textContent = `${category.charAt(0).toUpperCase() + category.slice(1)} Emails`;
+            header.textContent = `${capitalize(category)} Emails`;
             header.addEventListener('click', () => {
                 this.onTabChange?.(category);
             });
             this.tabHeaders[category] = header;
-            tabHeader.appendChild(header);
+            this.tabHeader.appendChild(header);
         });
 
         // Tab contents
-        const tabContentContainer = document.createElement('div');
         categories.forEach(category => {
             const tabContent = document.createElement('div');
             tabContent.className = 'bg-white rounded-lg shadow-lg p-6 hidden';
-            tabContent.id = `${category}-tab`;
 
             const title = document.createElement('h2');
             title.className = 'text-xl font-semibold mb-4';
-            title.textContent = `${category.charAt(0).toUpperCase() + category.slice(1)} Emails`;
+            title.textContent = `${capitalize(category)} Emails`;
 
             const stats = document.createElement('div');
             stats.className = 'mb-4 text-gray-600';
 
             const count = document.createElement('p');
-            count.id = `${category}-count`;
             count.textContent = 'Count: 0';
 
             const size = document.createElement('p');
-            size.id = `${category}-size`;
             size.textContent = 'Total size: 0 KB';
 
             const results = document.createElement('div');
             results.className = 'space-y-3 pr-2';
-            results.id = `${category}-results`;
+
+            const toolbar = document.createElement('div');
+            toolbar.className = 'flex gap-3 mb-4';
+            const actions = [
+                { label: 'Move to Trash', handler: () => this.trashSelected() },
+                { label: 'Archive', handler: () => this.archiveSelected() },
+                { label: 'Label', handler: () => this.labelSelected() }
+            ];
+            actions.forEach(action => {
+                const button = document.createElement('button');
+                button.className = 'btn-primary';
+                button.textContent = action.label;
+                button.addEventListener('click', action.handler);
+                this.actionButtons.push(button);
+                toolbar.appendChild(button);
+            });
+            this.toolbars.push(toolbar);
 
             stats.appendChild(count);
             stats.appendChild(size);
             tabContent.appendChild(title);
             tabContent.appendChild(stats);
+            tabContent.appendChild(toolbar);
             tabContent.appendChild(results);
 
             this.tabContents[category] = tabContent;
             this.resultContainers[category] = results;
-            tabContentContainer.appendChild(tabContent);
+            this.countElements[category] = count;
+            this.sizeElements[category] = size;
+            this.tabContentContainer.appendChild(tabContent);
         });
+        this.updateActionButtons();
+    }
 
-        section.appendChild(tabHeader);
-        section.appendChild(tabContentContainer);
+    public setCanModify(modify: boolean): void {
+        this.canModify = modify;
+        this.updateActionButtons();
+    }
 
-        return section;
+    private updateActionButtons(): void {
+        this.toolbars.forEach(toolbar => {
+            toolbar.style.display = this.canModify ? 'flex' : 'none';
+        });
+        this.actionButtons.forEach(button => {
+            button.disabled = this.selected.size === 0;
+        });
+    }
+
+    private async trashSelected(): Promise<void> {
+        const ids = Array.from(this.selected);
+        if (!confirm(`Move ${ids.length} messages to the Trash? Gmail deletes them for good after 30 days.`)) {
+            return;
+        }
+        this.actionButtons.forEach(button => button.disabled = true);
+        await this.onTrash?.(ids);
+    }
+
+    private async archiveSelected(): Promise<void> {
+        const ids = Array.from(this.selected);
+        this.actionButtons.forEach(button => button.disabled = true);
+        await this.onArchive?.(ids);
+        this.clearSelection();
+    }
+
+    private async labelSelected(): Promise<void> {
+        const ids = Array.from(this.selected);
+        const label = prompt(`Label to apply to ${ids.length} messages`, 'To review');
+        if (!label) {
+            return;
+        }
+        this.actionButtons.forEach(button => button.disabled = true);
+        await this.onLabel?.(ids, label);
+        this.clearSelection();
+    }
+
+    private clearSelection(): void {
+        this.selected.clear();
+        Object.values(this.resultContainers).forEach(container => {
+            container.querySelectorAll('input[type=checkbox]').forEach(checkbox => {
+                (checkbox as HTMLInputElement).checked = false;
+            });
+        });
+        this.updateActionButtons();
     }
 
     public switchToTab(category: string): void {
@@ -93,8 +182,8 @@ export default class TabsSectionComponent {
 
     public updateStats(categorizedMessages: CategorizedMessages): void {
         Object.entries(categorizedMessages).forEach(([category, messages]) => {
-            const countElement = document.getElementById(`${category}-count`);
-            const sizeElement = document.getElementById(`${category}-size`);
+            const countElement = this.countElements[category];
+            const sizeElement = this.sizeElements[category];
             const headingElement = this.tabHeaders[category];
 
             const totalSize = calculateTotalSize(messages);
@@ -102,11 +191,19 @@ export default class TabsSectionComponent {
 
             if (countElement) countElement.textContent = `Count: ${messages.length}`;
             if (sizeElement) sizeElement.textContent = `Total size: ${formattedSize}`;
-            if (headingElement) headingElement.textContent = `${category.charAt(0).toUpperCase() + category.slice(1)} (${formattedSize})`;
+            if (headingElement) headingElement.textContent = `${capitalize(category)} (${formattedSize})`;
         });
     }
 
     public updateResults(categorizedMessages: CategorizedMessages): void {
+        // Only keep selected the messages which are still listed
+        const listed = new Set<string>();
+        Object.values(categorizedMessages).forEach(messages => {
+            messages.forEach(item => listed.add(item.id));
+        });
+        this.selected = new Set(Array.from(this.selected).filter(id => listed.has(id)));
+        this.updateActionButtons();
+
         Object.entries(categorizedMessages).forEach(([category, messages]) => {
             const container = this.resultContainers[category];
             if (container) {
@@ -125,6 +222,22 @@ export default class TabsSectionComponent {
         const flexContainer = document.createElement('div');
         flexContainer.className = 'flex items-center justify-between';
 
+        if (this.canModify) {
+            const checkbox = document.createElement('input');
+            checkbox.type = 'checkbox';
+            checkbox.className = 'mr-3';
+            checkbox.checked = this.selected.has(item.id);
+            checkbox.addEventListener('change', () => {
+                if (checkbox.checked) {
+                    this.selected.add(item.id);
+                } else {
+                    this.selected.delete(item.id);
+                }
+                this.updateActionButtons();
+            });
+            flexContainer.appendChild(checkbox);
+        }
+
         const contentDiv = document.createElement('div');
         contentDiv.className = 'flex-1 min-w-0';
 
@@ -137,12 +250,17 @@ export default class TabsSectionComponent {
         messageSubject.className = 'font-semibold truncate text-lg';
         messageSubject.textContent = item.title;
 
+        const messageSender = document.createElement('p');
+        messageSender.className = 'text-sm text-gray-600 truncate';
+        messageSender.textContent = item.sender;
+
         const sizeSpan = document.createElement('span');
         sizeSpan.className = 'size-badge';
         sizeSpan.textContent = formatSize(item.size);
 
         link.appendChild(messageSubject);
         contentDiv.appendChild(link);
+        contentDiv.appendChild(messageSender);
         flexContainer.appendChild(contentDiv);
         flexContainer.appendChild(sizeSpan);
         div.appendChild(flexContainer);
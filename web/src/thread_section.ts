@@ -0,0 +1,83 @@
+import { ThreadTotal } from "./types.js";
+import { formatSize } from "./utils.js";
+
+export default class ThreadSectionComponent {
+    private resultsContainer: HTMLDivElement;
+    public onInspect?: (threadId: string) => Promise<ThreadTotal | null>;
+
+    constructor() {
+        this.resultsContainer = document.createElement('div');
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Largest Threads';
+
+        this.resultsContainer.className = 'space-y-3 pr-2';
+
+        section.appendChild(title);
+        section.appendChild(this.resultsContainer);
+
+        return section;
+    }
+
+    public updateThreads(threads: ThreadTotal[]): void {
+        this.resultsContainer.innerHTML = '';
+        threads.forEach(thread => {
+            this.resultsContainer.appendChild(this.createThreadElement(thread));
+        });
+    }
+
+    private createThreadElement(thread: ThreadTotal): HTMLDivElement {
+        const div = document.createElement('div');
+        div.className = 'message-card';
+
+        const flexContainer = document.createElement('div');
+        flexContainer.className = 'flex items-center justify-between';
+
+        const contentDiv = document.createElement('div');
+        contentDiv.className = 'flex-1 min-w-0';
+
+        const link = document.createElement('a');
+        link.className = 'text-gray-900 hover:text-blue-600 transition-colors';
+        link.href = `https://mail.google.com/mail/u/0/#all/${thread.thread_id}`;
+        link.target = '_blank';
+
+        const subject = document.createElement('p');
+        subject.className = 'font-semibold truncate text-lg';
+        subject.textContent = thread.subject;
+
+        const count = document.createElement('p');
+        count.className = 'text-sm text-gray-600';
+        count.textContent = `${thread.message_count} scanned messages`;
+
+        const sizeSpan = document.createElement('span');
+        sizeSpan.className = 'size-badge';
+        sizeSpan.textContent = formatSize(thread.total_size);
+
+        const inspectButton = document.createElement('button');
+        inspectButton.className = 'text-sm text-blue-600 ml-2';
+        inspectButton.textContent = 'Whole thread';
+        inspectButton.addEventListener('click', async () => {
+            const fullThread = await this.onInspect?.(thread.thread_id);
+            if (fullThread) {
+                count.textContent = `${fullThread.message_count} messages in thread`;
+                sizeSpan.textContent = formatSize(fullThread.total_size);
+                inspectButton.remove();
+            }
+        });
+
+        link.appendChild(subject);
+        contentDiv.appendChild(link);
+        contentDiv.appendChild(count);
+        flexContainer.appendChild(contentDiv);
+        flexContainer.appendChild(inspectButton);
+        flexContainer.appendChild(sizeSpan);
+        div.appendChild(flexContainer);
+
+        return div;
+    }
+}
@@ -0,0 +1,150 @@
+import { Snapshot, SnapshotDiff } from "./types.js";
+import { formatSize, formatSizeDelta } from "./utils.js";
+
+export default class TrendSectionComponent {
+    private chart: any = null;
+    private canvas: HTMLCanvasElement;
+    private fromSelect: HTMLSelectElement;
+    private toSelect: HTMLSelectElement;
+    private compareButton: HTMLButtonElement;
+    private diffContainer: HTMLDivElement;
+    public onCompare?: (from: number, to: number) => void;
+
+    constructor() {
+        this.canvas = document.createElement('canvas');
+        this.canvas.height = 200;
+        this.fromSelect = document.createElement('select');
+        this.toSelect = document.createElement('select');
+        this.compareButton = document.createElement('button');
+        this.diffContainer = document.createElement('div');
+        this.setupEventListeners();
+    }
+
+    private setupEventListeners(): void {
+        this.compareButton.addEventListener('click', () => {
+            this.onCompare?.(parseInt(this.fromSelect.value), parseInt(this.toSelect.value));
+        });
+    }
+
+    public render(): HTMLDivElement {
+        const section = document.createElement('div');
+        section.className = 'bg-white rounded-lg shadow-lg p-6 mt-8';
+
+        const title = document.createElement('h2');
+        title.className = 'text-xl font-semibold mb-4';
+        title.textContent = 'Mailbox Size Over Time';
+
+        const compareContainer = document.createElement('div');
+        compareContainer.className = 'flex gap-3 flex-row items-center mt-4';
+
+        const fromLabel = document.createElement('span');
+        fromLabel.textContent = 'Compare';
+        const toLabel = document.createElement('span');
+        toLabel.textContent = 'with';
+
+        this.fromSelect.className = 'input-field flex-1';
+        this.toSelect.className = 'input-field flex-1';
+        this.compareButton.className = 'btn-primary';
+        this.compareButton.textContent = 'Compare';
+        this.diffContainer.className = 'mt-4 text-gray-600';
+
+        compareContainer.appendChild(fromLabel);
+        compareContainer.appendChild(this.fromSelect);
+        compareContainer.appendChild(toLabel);
+        compareContainer.appendChild(this.toSelect);
+        compareContainer.appendChild(this.compareButton);
+
+        section.appendChild(title);
+        section.appendChild(this.canvas);
+        section.appendChild(compareContainer);
+        section.appendChild(this.diffContainer);
+
+        return section;
+    }
+
+    public updateSnapshots(snapshots: Snapshot[]): void {
+        const labels = snapshots.map(snapshot => new Date(snapshot.taken_at).toLocaleString());
+
+        [this.fromSelect, this.toSelect].forEach(select => {
+            select.innerHTML = '';
+            snapshots.forEach((snapshot, index) => {
+                const option = document.createElement('option');
+                option.value = snapshot.id.toString();
+                option.textContent = labels[index];
+                select.appendChild(option);
+            });
+        });
+        if (snapshots.length > 0) {
+            this.fromSelect.value = snapshots[0].id.toString();
+            this.toSelect.value = snapshots[snapshots.length - 1].id.toString();
+        }
+
+        const ctx = this.canvas.getContext('2d');
+        if (!ctx) return;
+
+        if (this.chart) {
+            this.chart.destroy();
+        }
+
+        this.chart = new (window as any).Chart(ctx, {
+            type: 'line',
+            data: {
+                labels: labels,
+                datasets: [{
+                    label: 'Scanned size (MB)',
+                    data: snapshots.map(snapshot => snapshot.total_size / (1024 * 1024)),
+                    borderColor: '#3b82f6',
+                    fill: false
+                }]
+            },
+            options: {
+                responsive: true,
+                plugins: {
+                    legend: {
+                        position: 'bottom'
+                    }
+                }
+            }
+        });
+    }
+
+    public showDiff(diff: SnapshotDiff): void {
+        this.diffContainer.innerHTML = '';
+
+        const total = document.createElement('p');
+        total.className = 'font-medium';
+        total.textContent = `Scanned size: ${formatSizeDelta(diff.total_size)}, messages in mailbox: ${diff.messages_total >= 0 ? '+' : ''}${diff.messages_total}`;
+        this.diffContainer.appendChild(total);
+
+        const groups = [
+            { title: 'By size', totals: diff.buckets },
+            { title: 'By label', totals: diff.labels },
+            { title: 'By sender', totals: diff.senders }
+        ];
+        groups.forEach(group => {
+            const entries = Object.entries(group.totals)
+                .sort((a, b) => Math.abs(b[1]) - Math.abs(a[1]))
+                .slice(0, 10);
+            if (entries.length === 0) return;
+
+            const heading = document.createElement('h3');
+            heading.className = 'font-semibold mt-3';
+            heading.textContent = group.title;
+
+            const list = document.createElement('ul');
+            list.className = 'list-disc pl-6';
+            entries.forEach(([key, delta]) => {
+                const li = document.createElement('li');
+                li.textContent = `${key}: ${formatSizeDelta(delta)}`;
+                list.appendChild(li);
+            });
+
+            this.diffContainer.appendChild(heading);
+            this.diffContainer.appendChild(list);
+        });
+    }
+
+    public showLatest(snapshot: Snapshot): void {
+        this.diffContainer.textContent = `Latest scan: ${snapshot.scanned_count} of ${snapshot.messages_total} messages, ${formatSize(snapshot.total_size)}`;
+    }
+}
@@ -3,14 +3,263 @@ export interface ProfileData {
 }
 
 export interface MessageItem {
+    id: string;
     thread_id: string;
     title: string;
+    sender: string;
     size: number;
+    bucket: string;
 }
 
 export interface CategorizedMessages {
-    small: MessageItem[];
-    medium: MessageItem[];
-    large: MessageItem[];
+    [bucket: string]: MessageItem[];
 }
 
+export interface SizeBucket {
+    name: string;
+    min_size: number;
+}
+
+export interface SizeClassifier {
+    buckets: SizeBucket[];
+}
+
+export interface Snapshot {
+    id: number;
+    taken_at: number;
+    messages_total: number;
+    scanned_count: number;
+    total_size: number;
+    buckets: { [key: string]: number };
+    labels: { [key: string]: number };
+    senders: { [key: string]: number };
+}
+
+export interface SnapshotDiff {
+    from: number;
+    to: number;
+    messages_total: number;
+    total_size: number;
+    buckets: { [key: string]: number };
+    labels: { [key: string]: number };
+    senders: { [key: string]: number };
+}
+
+export interface ThreadTotal {
+    thread_id: string;
+    subject: string;
+    message_count: number;
+    total_size: number;
+    latest_date: number;
+}
+
+export interface SenderTotal {
+    sender: string;
+    message_count: number;
+    total_size: number;
+}
+
+export interface AttachmentEntry {
+    message_id: string;
+    thread_id: string;
+    subject: string;
+    kind: string;
+    part_id: string;
+    filename: string;
+    mime_type: string;
+    size: number;
+    attachment_id: string | null;
+}
+
+export interface SavedAttachment {
+    message_id: string;
+    thread_id: string;
+    part_id: string;
+    subject: string;
+    sender: string;
+    date: number;
+    filename: string;
+    mime_type: string;
+    size: number;
+    path: string;
+    sha256: string;
+    saved_at: number;
+}
+
+export interface SaveReport {
+    folder: string;
+    manifest: string;
+    saved: SavedAttachment[];
+    failed: string[];
+}
+
+export interface RemovedPart {
+    part_id: string;
+    filename: string;
+    mime_type: string;
+    size: number;
+}
+
+export interface StripReport {
+    stripped: { original_id: string; new_id: string; removed: RemovedPart[] }[];
+    unchanged: string[];
+    failed: string[];
+}
+
+export interface KindTotal {
+    kind: string;
+    count: number;
+    total_size: number;
+}
+
+export interface DuplicateCopy {
+    message_id: string;
+    thread_id: string;
+    subject: string;
+    part_id: string;
+    attachment_id: string | null;
+}
+
+export interface DuplicateGroup {
+    filename: string;
+    mime_type: string;
+    size: number;
+    verified: boolean;
+    copies: DuplicateCopy[];
+    redundant_size: number;
+}
+
+export interface DuplicateReport {
+    groups: DuplicateGroup[];
+    redundant_size: number;
+}
+
+export interface LabelTotal {
+    label_id: string;
+    name: string;
+    message_count: number;
+    total_size: number;
+}
+
+export interface AgeTotal {
+    period: string;
+    message_count: number;
+    total_size: number;
+    share: number;
+}
+
+export type RuleCondition =
+    | { field: 'sender'; pattern: string }
+    | { field: 'label'; label: string }
+    | { field: 'older_than'; days: number }
+    | { field: 'newer_than'; days: number }
+    | { field: 'larger_than'; bytes: number }
+    | { field: 'smaller_than'; bytes: number }
+    | { field: 'attachment_kind'; kind: string }
+    | { field: 'subject'; regex: string };
+
+export interface Rule {
+    name: string;
+    category: string;
+    conditions: RuleCondition[];
+}
+
+export interface CategoryTotal {
+    category: string;
+    message_count: number;
+    total_size: number;
+}
+
+export interface ListTotal {
+    list_id: string;
+    name: string;
+    sender: string;
+    message_count: number;
+    total_size: number;
+    unsubscribe: string;
+    unsubscribe_post: string;
+    latest_date: number;
+}
+
+export interface UnsubscribePlan {
+    list_id: string;
+    name: string;
+    method: 'one_click' | 'link' | 'mailto' | 'none';
+    url: string;
+}
+
+export interface BulkSelection {
+    query?: string;
+    bucket?: string;
+    sender?: string;
+    group?: string;
+    category?: string;
+}
+
+export interface BulkTrashReport {
+    dry_run: boolean;
+    message_count: number;
+    total_size: number;
+    messages: MessageItem[];
+    trashed: number;
+    failed: number;
+}
+
+export interface MessageChange {
+    id: string;
+    labels_before: string[];
+    labels_after: string[];
+}
+
+export interface JournalEntry {
+    id: number;
+    action: string;
+    performed_at: number;
+    changes: MessageChange[];
+    thread_ids: string[];
+    created_ids: string[];
+    undone: boolean;
+}
+
+export interface UndoReport {
+    entry_id: number;
+    restored: number;
+    failed: number;
+}
+
+export interface FilterRequest {
+    sender?: string;
+    list_id?: string;
+    query?: string;
+    action: string;
+    label?: string;
+}
+
+export interface FilterPreview {
+    dry_run: boolean;
+    criteria: { from?: string; query?: string };
+    search: string;
+    description: string;
+    matching_estimate: number | null;
+    filter_id: string | null;
+}
+
+export interface ArchivedMessage {
+    message_id: string;
+    thread_id: string;
+    subject: string;
+    sender: string;
+    date: number;
+    labels: string[];
+    size: number;
+    format: string;
+    path: string;
+    archived_at: number;
+}
+
+export interface ArchiveReport {
+    folder: string;
+    index: string;
+    archived: ArchivedMessage[];
+    failed: string[];
+}
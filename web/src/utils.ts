@@ -11,3 +11,12 @@ export function formatSize(sizeInB: number): string {
 export function calculateTotalSize(messages: MessageItem[]): number {
     return messages.reduce((sum, item) => sum + item.size, 0);
 }
+
+export function formatSizeDelta(deltaInB: number): string {
+    const sign = deltaInB < 0 ? '-' : '+';
+    return `${sign}${formatSize(Math.abs(deltaInB))}`;
+}
+
+export function capitalize(text: string): string {
+    return text.charAt(0).toUpperCase() + text.slice(1);
+}